@@ -0,0 +1,92 @@
+//! Запись файлов, переживающая аварийное завершение.
+
+use std::{
+    io::{self,Write},
+    fs::{File,read,rename,create_dir_all},
+    path::{Path,PathBuf},
+    ffi::OsString,
+};
+
+/// Путь резервной копии: `[путь].bak`.
+pub fn backup_path<P:AsRef<Path>>(path:P)->PathBuf{
+    with_suffix(path.as_ref(),".bak")
+}
+
+/// Атомарная запись файла.
+///
+/// Данные записываются во временный файл `[путь].tmp` и сбрасываются на диск,
+/// предыдущий файл переименовывается в резервную копию (см. `backup_path`),
+/// после чего временный файл переименовывается в `path`.
+/// При сбое на любом шаге остаётся либо прежний файл, либо резервная копия.
+///
+/// Предыдущий файл становится резервной копией, только если `is_valid` его принимает,
+/// иначе повреждённый файл заменяется, а прежняя копия остаётся.
+pub fn write_atomic<P:AsRef<Path>,F:Fn(&[u8])->bool>(path:P,data:&[u8],is_valid:F)->io::Result<()>{
+    let path=path.as_ref();
+
+    let directory=path.parent().filter(|directory|!directory.as_os_str().is_empty());
+    if let Some(directory)=directory{
+        create_dir_all(directory)?;
+    }
+
+    let temporary=with_suffix(path,".tmp");
+    {
+        let mut file=File::create(&temporary)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+
+    if path.exists() && read(path).is_ok_and(|previous|is_valid(&previous)){
+        rename(path,backup_path(path))?;
+    }
+    rename(&temporary,path)?;
+
+    // Сохранение переименований (на Windows папку открыть нельзя)
+    if let Some(directory)=directory{
+        if let Ok(directory)=File::open(directory){
+            let _=directory.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+fn with_suffix(path:&Path,suffix:&str)->PathBuf{
+    let mut path:OsString=path.as_os_str().to_os_string();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use std::fs::{write,remove_dir_all};
+
+    fn is_valid(data:&[u8])->bool{
+        data.starts_with(b"ok")
+    }
+
+    #[test]
+    fn backup_only_valid(){
+        let directory=std::env::temp_dir().join(format!("visual_novel_atomic_{}",std::process::id()));
+        let path=directory.join("settings");
+        let backup=backup_path(&path);
+
+        write_atomic(&path,b"ok 1",is_valid).unwrap();
+        assert_eq!(read(&path).unwrap(),b"ok 1");
+        assert!(!backup.exists());
+
+        write_atomic(&path,b"ok 2",is_valid).unwrap();
+        assert_eq!(read(&backup).unwrap(),b"ok 1");
+
+        // Повреждённый файл не заменяет рабочую копию
+        write(&path,b"broken").unwrap();
+        write_atomic(&path,b"ok 3",is_valid).unwrap();
+        assert_eq!(read(&path).unwrap(),b"ok 3");
+        assert_eq!(read(&backup).unwrap(),b"ok 1");
+        assert!(!with_suffix(&path,".tmp").exists());
+
+        remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,262 @@
+//! Двоичные данные с явной шириной полей (big-endian).
+
+use std::fmt;
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum BinaryError{
+    /// Данные закончились раньше поля.
+    UnexpectedEnd,
+    /// Строка не в UTF-8.
+    InvalidUtf8,
+}
+
+impl fmt::Display for BinaryError{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            BinaryError::UnexpectedEnd=>write!(f,"данные закончились раньше поля"),
+            BinaryError::InvalidUtf8=>write!(f,"строка не в UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError{}
+
+/// Запись полей в буфер.
+pub struct BinaryWriter{
+    buffer:Vec<u8>,
+}
+
+impl BinaryWriter{
+    pub fn new()->BinaryWriter{
+        Self{
+            buffer:Vec::new(),
+        }
+    }
+
+    pub fn write_u8(&mut self,value:u8){
+        self.buffer.push(value)
+    }
+
+    pub fn write_bool(&mut self,value:bool){
+        self.buffer.push(value as u8)
+    }
+
+    pub fn write_u16(&mut self,value:u16){
+        self.buffer.extend_from_slice(&value.to_be_bytes())
+    }
+
+    pub fn write_u32(&mut self,value:u32){
+        self.buffer.extend_from_slice(&value.to_be_bytes())
+    }
+
+    pub fn write_u64(&mut self,value:u64){
+        self.buffer.extend_from_slice(&value.to_be_bytes())
+    }
+
+    pub fn write_i64(&mut self,value:i64){
+        self.buffer.extend_from_slice(&value.to_be_bytes())
+    }
+
+    pub fn write_f32(&mut self,value:f32){
+        self.buffer.extend_from_slice(&value.to_be_bytes())
+    }
+
+    /// Байты без длины.
+    pub fn write_raw(&mut self,bytes:&[u8]){
+        self.buffer.extend_from_slice(bytes)
+    }
+
+    /// Длина (`u32`) и байты.
+    pub fn write_bytes(&mut self,bytes:&[u8]){
+        self.write_u32(bytes.len() as u32);
+        self.write_raw(bytes)
+    }
+
+    /// Длина в байтах (`u32`) и строка в UTF-8.
+    pub fn write_str(&mut self,string:&str){
+        self.write_bytes(string.as_bytes())
+    }
+
+    pub fn as_bytes(&self)->&[u8]{
+        &self.buffer
+    }
+
+    pub fn into_bytes(self)->Vec<u8>{
+        self.buffer
+    }
+}
+
+impl Default for BinaryWriter{
+    fn default()->BinaryWriter{
+        BinaryWriter::new()
+    }
+}
+
+/// Чтение полей из среза.
+pub struct BinaryReader<'a>{
+    data:&'a [u8],
+    position:usize,
+}
+
+impl<'a> BinaryReader<'a>{
+    pub fn new(data:&'a [u8])->BinaryReader<'a>{
+        Self{
+            data,
+            position:0,
+        }
+    }
+
+    /// Количество непрочитанных байтов.
+    pub fn remaining(&self)->usize{
+        self.data.len()-self.position
+    }
+
+    pub fn is_end(&self)->bool{
+        self.remaining()==0
+    }
+
+    /// Байты без длины.
+    pub fn read_raw(&mut self,len:usize)->Result<&'a [u8],BinaryError>{
+        if self.remaining()<len{
+            return Err(BinaryError::UnexpectedEnd)
+        }
+
+        let bytes=&self.data[self.position..self.position+len];
+        self.position+=len;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self)->Result<u8,BinaryError>{
+        Ok(self.read_raw(1)?[0])
+    }
+
+    pub fn read_bool(&mut self)->Result<bool,BinaryError>{
+        self.read_u8().map(|value|value!=0)
+    }
+
+    pub fn read_u16(&mut self)->Result<u16,BinaryError>{
+        let mut buffer=[0u8;2];
+        buffer.copy_from_slice(self.read_raw(2)?);
+        Ok(u16::from_be_bytes(buffer))
+    }
+
+    pub fn read_u32(&mut self)->Result<u32,BinaryError>{
+        let mut buffer=[0u8;4];
+        buffer.copy_from_slice(self.read_raw(4)?);
+        Ok(u32::from_be_bytes(buffer))
+    }
+
+    pub fn read_u64(&mut self)->Result<u64,BinaryError>{
+        let mut buffer=[0u8;8];
+        buffer.copy_from_slice(self.read_raw(8)?);
+        Ok(u64::from_be_bytes(buffer))
+    }
+
+    pub fn read_i64(&mut self)->Result<i64,BinaryError>{
+        self.read_u64().map(|value|value as i64)
+    }
+
+    pub fn read_f32(&mut self)->Result<f32,BinaryError>{
+        self.read_u32().map(f32::from_bits)
+    }
+
+    /// Длина (`u32`) и байты.
+    pub fn read_bytes(&mut self)->Result<&'a [u8],BinaryError>{
+        let len=self.read_u32()? as usize;
+        self.read_raw(len)
+    }
+
+    /// Длина в байтах (`u32`) и строка в UTF-8.
+    pub fn read_string(&mut self)->Result<String,BinaryError>{
+        let bytes=self.read_bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_|BinaryError::InvalidUtf8)
+    }
+}
+
+/// Контрольная сумма CRC-32 (IEEE).
+pub fn crc32(data:&[u8])->u32{
+    let mut crc=!0u32;
+    for &byte in data{
+        crc^=byte as u32;
+        for _ in 0..8{
+            let mask=(crc&1).wrapping_neg();
+            crc=(crc>>1)^(0xEDB88320&mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn round_trip(){
+        let mut writer=BinaryWriter::new();
+        writer.write_u8(7);
+        writer.write_bool(true);
+        writer.write_u16(0xBEEF);
+        writer.write_u32(0xDEADBEEF);
+        writer.write_u64(u64::MAX-1);
+        writer.write_i64(-42);
+        writer.write_f32(1.5f32);
+        writer.write_bytes(&[1,2,3]);
+        writer.write_str("страница");
+        writer.write_raw(b"end");
+
+        let bytes=writer.into_bytes();
+        let mut reader=BinaryReader::new(&bytes);
+
+        assert_eq!(reader.read_u8(),Ok(7));
+        assert_eq!(reader.read_bool(),Ok(true));
+        assert_eq!(reader.read_u16(),Ok(0xBEEF));
+        assert_eq!(reader.read_u32(),Ok(0xDEADBEEF));
+        assert_eq!(reader.read_u64(),Ok(u64::MAX-1));
+        assert_eq!(reader.read_i64(),Ok(-42));
+        assert_eq!(reader.read_f32(),Ok(1.5f32));
+        assert_eq!(reader.read_bytes(),Ok(&[1u8,2,3][..]));
+        assert_eq!(reader.read_string(),Ok("страница".to_string()));
+        assert_eq!(reader.read_raw(3),Ok(&b"end"[..]));
+        assert!(reader.is_end());
+    }
+
+    #[test]
+    fn big_endian(){
+        let mut writer=BinaryWriter::new();
+        writer.write_u32(0x01020304);
+        assert_eq!(writer.as_bytes(),&[1,2,3,4]);
+    }
+
+    #[test]
+    fn truncated(){
+        let mut writer=BinaryWriter::new();
+        writer.write_str("страница");
+        let bytes=writer.into_bytes();
+
+        // Обрезанная строка и обрезанная длина
+        assert_eq!(BinaryReader::new(&bytes[..bytes.len()-1]).read_string(),Err(BinaryError::UnexpectedEnd));
+        assert_eq!(BinaryReader::new(&bytes[..3]).read_string(),Err(BinaryError::UnexpectedEnd));
+
+        // Длина больше данных не читается
+        assert_eq!(BinaryReader::new(&[0xFF,0xFF,0xFF,0xFF,0]).read_bytes(),Err(BinaryError::UnexpectedEnd));
+
+        let mut reader=BinaryReader::new(&[1,2,3]);
+        assert_eq!(reader.read_u32(),Err(BinaryError::UnexpectedEnd));
+        // Позиция не сдвигается после ошибки
+        assert_eq!(reader.read_u16(),Ok(0x0102));
+        assert_eq!(reader.remaining(),1);
+    }
+
+    #[test]
+    fn invalid_utf8(){
+        let mut writer=BinaryWriter::new();
+        writer.write_bytes(&[0xFF,0xFE]);
+        assert_eq!(BinaryReader::new(writer.as_bytes()).read_string(),Err(BinaryError::InvalidUtf8));
+    }
+
+    #[test]
+    fn crc32_check_value(){
+        assert_eq!(crc32(b"123456789"),0xCBF43926);
+        assert_eq!(crc32(b""),0);
+    }
+}
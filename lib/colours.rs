@@ -34,4 +34,6 @@ pub const Bleak_orange:Colour=[1.0, 0.545, 0.349, 1.0];
 // Специальные \\
 pub const Head_main_menu_colour:Colour=White;
 
-pub const Pause_menu_background_colour:Colour=[0.1,0.2,0.3,1.0]; // Серо-синий
\ No newline at end of file
+pub const Pause_menu_background_colour:Colour=[0.1,0.2,0.3,1.0]; // Серо-синий
+
+pub const Inactive_character_colour:Colour=[0.6,0.6,0.6,1.0]; // Затемнение молчащих персонажей
\ No newline at end of file
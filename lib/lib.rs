@@ -6,6 +6,18 @@ pub mod colours;
 
 pub mod loading_flag;
 
+pub mod story;
+
+pub mod binary;
+
+pub mod atomic_file;
+
+pub mod manifest;
+
+pub mod resources;
+
+pub mod mp3;
+
 // Выравнивание
 #[derive(Clone)]
 pub struct Align{
@@ -21,7 +21,7 @@ impl LoadingFlag{
     }
 
     pub fn get_state(&self)->ThreadState{
-        self.state.as_ref().clone()
+        *self.state.as_ref()
     }
 
     pub fn ptr(&mut self)->LoadingFlagSmartPtr{
@@ -31,6 +31,12 @@ impl LoadingFlag{
     }
 }
 
+impl Default for LoadingFlag{
+    fn default()->LoadingFlag{
+        LoadingFlag::new()
+    }
+}
+
 pub struct LoadingFlagSmartPtr{
     ptr:*mut ThreadState,
 }
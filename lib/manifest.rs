@@ -0,0 +1,573 @@
+//! Список ресурсов игры (`./resources/manifest.txt`).
+//!
+//! ```text
+//! fonts {
+//!     [название] = [путь]
+//! }
+//! audio {
+//!     [название] = [путь] (music|sound|voice)
+//! }
+//! images {
+//!     [название] = [путь] (interface|wallpaper|decoration)
+//! }
+//! ```
+//! Пути указываются через `/` относительно папки файла списка.
+//! Категория трека по умолчанию - `sound`, назначение картинки - `interface`.
+
+use crate::{
+    resources::{Resources,join},
+    story::indent,
+};
+
+use std::{
+    fmt,
+    io,
+    collections::HashMap,
+    path::{Path,PathBuf},
+};
+
+/// Категория аудио трека.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum TrackCategory{
+    /// Фоновая музыка, повторяется бесконечно
+    Music,
+    /// Звук, проигрывается один раз
+    Sound,
+    /// Озвучка строки: название - `[диалог]/[метка]`,
+    /// где метка стоит перед строкой; авточтение ждёт её окончания
+    Voice,
+}
+
+impl TrackCategory{
+    pub fn parse(name:&str)->Option<TrackCategory>{
+        match name{
+            "music"=>Some(TrackCategory::Music),
+            "sound"=>Some(TrackCategory::Sound),
+            "voice"=>Some(TrackCategory::Voice),
+            _=>None,
+        }
+    }
+}
+
+/// Назначение картинки - определяет, как она загружается.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum ImageRole{
+    /// Элемент интерфейса, загружается как есть
+    Interface,
+    /// Обои, растягиваются на размер обоев
+    Wallpaper,
+    /// Украшение главного меню
+    Decoration,
+}
+
+impl ImageRole{
+    pub fn parse(name:&str)->Option<ImageRole>{
+        match name{
+            "interface"=>Some(ImageRole::Interface),
+            "wallpaper"=>Some(ImageRole::Wallpaper),
+            "decoration"=>Some(ImageRole::Decoration),
+            _=>None,
+        }
+    }
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct FontEntry{
+    pub name:String,
+    /// Путь в ресурсах
+    pub path:String,
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct TrackEntry{
+    pub name:String,
+    /// Путь в ресурсах
+    pub path:String,
+    pub category:TrackCategory,
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct ImageEntry{
+    pub name:String,
+    /// Путь в ресурсах
+    pub path:String,
+    pub role:ImageRole,
+}
+
+/// Разделы списка.
+const sections:[&str;3]=["fonts","audio","images"];
+
+/// Список ресурсов в порядке записи.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Manifest{
+    fonts:Vec<FontEntry>,
+    tracks:Vec<TrackEntry>,
+    images:Vec<ImageEntry>,
+}
+
+impl Manifest{
+    /// Чтение списка `name` из ресурсов и проверка наличия всех файлов.
+    pub fn load(resources:&Resources,name:&str)->Result<Manifest,ManifestError>{
+        let path=resources.path(name);
+        let source=match resources.read_to_string(name){
+            Ok(source)=>source,
+            Err(e)=>return Err(ManifestError::Io(path,e)),
+        };
+
+        let root=name.rsplit_once('/').map_or("",|(root,_)|root);
+        let manifest=Manifest::parse(&source,&path,root)?;
+
+        let files=manifest.fonts.iter().map(|font|(&font.name,&font.path))
+                .chain(manifest.tracks.iter().map(|track|(&track.name,&track.path)))
+                .chain(manifest.images.iter().map(|image|(&image.name,&image.path)));
+
+        for (name,file) in files{
+            if !resources.exists(file){
+                return Err(ManifestError::MissingFile{
+                    name:name.clone(),
+                    path:resources.path(file),
+                })
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// `path` используется только для сообщений об ошибках,
+    /// пути ресурсов отсчитываются от `root`.
+    pub fn parse(source:&str,path:&Path,root:&str)->Result<Manifest,ManifestError>{
+        let mut manifest=Manifest::default();
+
+        // Строки заголовков разделов
+        let mut section_lines:HashMap<&str,usize>=HashMap::new();
+
+        let mut lines=source.lines()
+                .enumerate()
+                .map(|(n,line)|(n+1,line.trim_start_matches('\u{feff}')));
+
+        while let Some((n,line))=lines.next(){
+            let trimmed=line.trim();
+            if trimmed.is_empty(){
+                continue
+            }
+
+            let error=|kind|ManifestError::parse(path,n,line,indent(line),kind);
+
+            if trimmed=="}"{
+                return Err(error(ManifestErrorKind::UnexpectedSectionEnd))
+            }
+
+            let section=match trimmed.strip_suffix('{'){
+                Some(section)=>section.trim_end(),
+                None=>return Err(ManifestError::parse(path,n,line,line.trim_end().len(),ManifestErrorKind::ExpectedSectionStart)),
+            };
+
+            let section=match sections.iter().find(|&&name|name==section){
+                Some(&section)=>section,
+                None=>return Err(error(ManifestErrorKind::UnknownSection(section.to_string()))),
+            };
+
+            if let Some(&first_line)=section_lines.get(section){
+                return Err(error(ManifestErrorKind::DuplicateSection{
+                    section:section.to_string(),
+                    first_line,
+                }))
+            }
+            section_lines.insert(section,n);
+
+            let header=(n,line);
+
+            // Названия в разделе и строки, в которых они записаны
+            let mut names:HashMap<String,usize>=HashMap::new();
+
+            loop{
+                let (n,line)=match lines.next(){
+                    Some(line)=>line,
+                    None=>return Err(ManifestError::parse(path,header.0,header.1,indent(header.1),ManifestErrorKind::UnclosedSection)),
+                };
+
+                let trimmed=line.trim();
+                if trimmed.is_empty(){
+                    continue
+                }
+                if trimmed=="}"{
+                    break
+                }
+
+                let offset=indent(line);
+                let error=|kind|ManifestError::parse(path,n,line,offset,kind);
+
+                let (name,value)=match trimmed.split_once('='){
+                    Some((name,value))=>(name.trim(),value.trim()),
+                    None=>return Err(error(ManifestErrorKind::ExpectedEntry)),
+                };
+
+                if name.is_empty(){
+                    return Err(error(ManifestErrorKind::EmptyName))
+                }
+
+                if let Some(&first_line)=names.get(name){
+                    return Err(error(ManifestErrorKind::DuplicateName{
+                        name:name.to_string(),
+                        first_line,
+                    }))
+                }
+
+                // Категория или назначение в скобках
+                let (file,kind)=match value.strip_suffix(')').and_then(|rest|rest.rsplit_once('(')){
+                    Some((file,kind))=>(file.trim_end(),Some(kind.trim())),
+                    None=>(value,None),
+                };
+
+                if file.is_empty(){
+                    return Err(error(ManifestErrorKind::EmptyPath(name.to_string())))
+                }
+
+                let file=join(root,file);
+
+                match section{
+                    "fonts"=>{
+                        if let Some(kind)=kind{
+                            return Err(error(ManifestErrorKind::UnexpectedKind(kind.to_string())))
+                        }
+
+                        manifest.fonts.push(FontEntry{
+                            name:name.to_string(),
+                            path:file,
+                        });
+                    }
+
+                    "audio"=>{
+                        let category=match kind{
+                            Some(kind)=>match TrackCategory::parse(kind){
+                                Some(category)=>category,
+                                None=>return Err(error(ManifestErrorKind::UnknownCategory(kind.to_string()))),
+                            }
+                            None=>TrackCategory::Sound,
+                        };
+
+                        manifest.tracks.push(TrackEntry{
+                            name:name.to_string(),
+                            path:file,
+                            category,
+                        });
+                    }
+
+                    _=>{
+                        let role=match kind{
+                            Some(kind)=>match ImageRole::parse(kind){
+                                Some(role)=>role,
+                                None=>return Err(error(ManifestErrorKind::UnknownRole(kind.to_string()))),
+                            }
+                            None=>ImageRole::Interface,
+                        };
+
+                        manifest.images.push(ImageEntry{
+                            name:name.to_string(),
+                            path:file,
+                            role,
+                        });
+                    }
+                }
+
+                names.insert(name.to_string(),n);
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// Проверка, что в списке есть все ресурсы, используемые игрой по названию.
+    pub fn require(&self,fonts:&[&str],tracks:&[&str],images:&[&str])->Result<(),ManifestError>{
+        let missing=|section:&'static str,name:&str|ManifestError::MissingEntry{
+            section,
+            name:name.to_string(),
+        };
+
+        for &name in fonts{
+            if self.font(name).is_none(){
+                return Err(missing("fonts",name))
+            }
+        }
+        for &name in tracks{
+            if self.track(name).is_none(){
+                return Err(missing("audio",name))
+            }
+        }
+        for &name in images{
+            if self.image(name).is_none(){
+                return Err(missing("images",name))
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn fonts(&self)->&[FontEntry]{
+        &self.fonts
+    }
+
+    pub fn tracks(&self)->&[TrackEntry]{
+        &self.tracks
+    }
+
+    pub fn images(&self)->&[ImageEntry]{
+        &self.images
+    }
+
+    pub fn font(&self,name:&str)->Option<&FontEntry>{
+        self.fonts.iter().find(|font|font.name==name)
+    }
+
+    pub fn track(&self,name:&str)->Option<&TrackEntry>{
+        self.tracks.iter().find(|track|track.name==name)
+    }
+
+    pub fn image(&self,name:&str)->Option<&ImageEntry>{
+        self.images.iter().find(|image|image.name==name)
+    }
+
+    /// Картинки с назначением `role` в порядке записи.
+    pub fn images_with_role(&self,role:ImageRole)->impl Iterator<Item=&ImageEntry>{
+        self.images.iter().filter(move|image|image.role==role)
+    }
+}
+
+/// Ошибка загрузки списка ресурсов.
+#[derive(Debug)]
+pub enum ManifestError{
+    /// Файл не удалось прочитать.
+    Io(PathBuf,io::Error),
+    /// Ошибка записи в строке `line` (начиная с 1), символе `column` (начиная с 1).
+    Parse{
+        path:PathBuf,
+        line:usize,
+        column:usize,
+        kind:ManifestErrorKind,
+    },
+    /// Нет файла ресурса `name`.
+    MissingFile{
+        name:String,
+        path:PathBuf,
+    },
+    /// Ресурс, используемый игрой, не указан в разделе `section`.
+    MissingEntry{
+        section:&'static str,
+        name:String,
+    },
+}
+
+impl ManifestError{
+    /// `offset` - смещение в байтах от начала строки `source_line`.
+    fn parse(path:&Path,line:usize,source_line:&str,offset:usize,kind:ManifestErrorKind)->ManifestError{
+        ManifestError::Parse{
+            path:path.to_path_buf(),
+            line,
+            column:source_line[..offset].chars().count()+1,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for ManifestError{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            ManifestError::Io(path,error)=>write!(f,"{}: {}",path.display(),error),
+            ManifestError::Parse{path,line,column,kind}=>write!(f,"{}:{}:{}: {}",path.display(),line,column,kind),
+            ManifestError::MissingFile{name,path}=>write!(f,"{}: файл ресурса `{}` не найден",path.display(),name),
+            ManifestError::MissingEntry{section,name}=>write!(f,"в разделе `{}` списка ресурсов не указан `{}`",section,name),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError{}
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum ManifestErrorKind{
+    /// Строка заголовка раздела не заканчивается на `{`.
+    ExpectedSectionStart,
+    /// Раздел не закрыт до конца файла.
+    UnclosedSection,
+    /// `}` вне раздела.
+    UnexpectedSectionEnd,
+    UnknownSection(String),
+    /// Раздел уже записан в строке `first_line`.
+    DuplicateSection{
+        section:String,
+        first_line:usize,
+    },
+    /// Ожидалась запись вида `[название] = [путь]`.
+    ExpectedEntry,
+    EmptyName,
+    EmptyPath(String),
+    /// Название уже использовано в разделе в строке `first_line`.
+    DuplicateName{
+        name:String,
+        first_line:usize,
+    },
+    UnknownCategory(String),
+    UnknownRole(String),
+    /// Для шрифтов не указывается категория.
+    UnexpectedKind(String),
+}
+
+impl fmt::Display for ManifestErrorKind{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            ManifestErrorKind::ExpectedSectionStart=>write!(f,"ожидалось `{{` в конце строки"),
+            ManifestErrorKind::UnclosedSection=>write!(f,"раздел не закрыт `}}`"),
+            ManifestErrorKind::UnexpectedSectionEnd=>write!(f,"лишняя `}}` вне раздела"),
+            ManifestErrorKind::UnknownSection(section)=>
+                write!(f,"неизвестный раздел `{}` (fonts, audio, images)",section),
+            ManifestErrorKind::DuplicateSection{section,first_line}=>
+                write!(f,"раздел `{}` уже записан в строке {}",section,first_line),
+            ManifestErrorKind::ExpectedEntry=>write!(f,"ожидалось `[название] = [путь]`"),
+            ManifestErrorKind::EmptyName=>write!(f,"пустое название ресурса"),
+            ManifestErrorKind::EmptyPath(name)=>write!(f,"пустой путь ресурса `{}`",name),
+            ManifestErrorKind::DuplicateName{name,first_line}=>
+                write!(f,"название `{}` уже использовано в строке {}",name,first_line),
+            ManifestErrorKind::UnknownCategory(category)=>
+                write!(f,"неизвестная категория трека `{}` (music, sound, voice)",category),
+            ManifestErrorKind::UnknownRole(role)=>
+                write!(f,"неизвестное назначение картинки `{}` (interface, wallpaper, decoration)",role),
+            ManifestErrorKind::UnexpectedKind(kind)=>write!(f,"для шрифта не указывается `({})`",kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use std::fs::{write,create_dir_all,remove_dir_all};
+
+    fn parse(source:&str)->Result<Manifest,ManifestError>{
+        Manifest::parse(source,Path::new("manifest.txt"),"")
+    }
+
+    /// Строка, символ и вид ошибки разбора.
+    fn error(source:&str)->(usize,usize,ManifestErrorKind){
+        match parse(source){
+            Err(ManifestError::Parse{line,column,kind,..})=>(line,column,kind),
+            result=>panic!("ожидалась ошибка разбора, а не {:?}",result),
+        }
+    }
+
+    const example:&str="\
+fonts {
+    main = fonts/main.font
+}
+
+audio {
+    theme = audio/theme.mp3 (music)
+    click = audio/click.mp3
+    Начало/встреча = audio/voice/встреча.mp3 (voice)
+}
+
+images {
+    cursor = images/cursor.png
+    menu = images/wallpapers/menu.png (wallpaper)
+    leaf = images/leaf.png (decoration)
+}
+";
+
+    #[test]
+    fn parse_manifest(){
+        let manifest=Manifest::parse(example,Path::new("manifest.txt"),"resources").unwrap();
+
+        assert_eq!(manifest.font("main").unwrap().path,"resources/fonts/main.font");
+        assert_eq!(manifest.track("theme").unwrap().category,TrackCategory::Music);
+        assert_eq!(manifest.track("click").unwrap().category,TrackCategory::Sound);
+        assert_eq!(manifest.track("Начало/встреча").unwrap().category,TrackCategory::Voice);
+        assert_eq!(manifest.image("cursor").unwrap().role,ImageRole::Interface);
+        assert_eq!(manifest.image("menu").unwrap().path,"resources/images/wallpapers/menu.png");
+
+        let wallpapers:Vec<&str>=manifest.images_with_role(ImageRole::Wallpaper).map(|image|image.name.as_str()).collect();
+        assert_eq!(wallpapers,["menu"]);
+
+        manifest.require(&["main"],&["theme","click"],&["cursor","menu","leaf"]).unwrap();
+    }
+
+    #[test]
+    fn malformed_sections(){
+        assert_eq!(error("fonts\n"),(1,6,ManifestErrorKind::ExpectedSectionStart));
+        assert_eq!(error("\n  }\n"),(2,3,ManifestErrorKind::UnexpectedSectionEnd));
+        assert_eq!(error("fonts {\n    main = fonts/main.font\n"),(1,1,ManifestErrorKind::UnclosedSection));
+        assert_eq!(error("music {\n}\n"),(1,1,ManifestErrorKind::UnknownSection("music".to_string())));
+
+        let kind=ManifestErrorKind::DuplicateSection{
+            section:"fonts".to_string(),
+            first_line:1,
+        };
+        assert_eq!(error("fonts {\n}\nfonts {\n}\n"),(3,1,kind));
+    }
+
+    #[test]
+    fn malformed_entries(){
+        assert_eq!(error("fonts {\n    main fonts/main.font\n}\n"),(2,5,ManifestErrorKind::ExpectedEntry));
+        assert_eq!(error("fonts {\n    = fonts/main.font\n}\n"),(2,5,ManifestErrorKind::EmptyName));
+        assert_eq!(error("audio {\n    theme = (music)\n}\n"),(2,5,ManifestErrorKind::EmptyPath("theme".to_string())));
+        assert_eq!(error("fonts {\n    main = fonts/main.font (music)\n}\n"),(2,5,ManifestErrorKind::UnexpectedKind("music".to_string())));
+    }
+
+    #[test]
+    fn duplicate_name(){
+        let kind=ManifestErrorKind::DuplicateName{
+            name:"click".to_string(),
+            first_line:2,
+        };
+        assert_eq!(error("audio {\n    click = a.mp3\n\n    click = b.mp3\n}\n"),(4,5,kind));
+
+        // Одинаковые названия в разных разделах допустимы
+        assert!(parse("audio {\n    menu = menu.mp3\n}\nimages {\n    menu = menu.png\n}\n").is_ok());
+    }
+
+    #[test]
+    fn unknown_category_or_role(){
+        assert_eq!(
+            error("audio {\n    theme = audio/theme.mp3 (loop)\n}\n"),
+            (2,5,ManifestErrorKind::UnknownCategory("loop".to_string()))
+        );
+        assert_eq!(
+            error("images {\n    cursor = images/cursor.png (icon)\n}\n"),
+            (2,5,ManifestErrorKind::UnknownRole("icon".to_string()))
+        );
+    }
+
+    #[test]
+    fn require_absent_entry(){
+        let manifest=parse(example).unwrap();
+
+        let missing=|result:Result<(),ManifestError>|match result{
+            Err(ManifestError::MissingEntry{section,name})=>(section,name),
+            result=>panic!("ожидалась ошибка отсутствия, а не {:?}",result),
+        };
+
+        assert_eq!(missing(manifest.require(&["dialogue"],&[],&[])),("fonts","dialogue".to_string()));
+        assert_eq!(missing(manifest.require(&["main"],&["screenshot"],&[])),("audio","screenshot".to_string()));
+        // Картинка есть только среди звуков
+        assert_eq!(missing(manifest.require(&[],&[],&["click"])),("images","click".to_string()));
+    }
+
+    #[test]
+    fn missing_file(){
+        let directory=std::env::temp_dir().join(format!("visual_novel_manifest_{}",std::process::id()));
+        create_dir_all(directory.join("fonts")).unwrap();
+        write(directory.join("fonts/main.font"),b"").unwrap();
+        write(directory.join("manifest.txt"),"fonts {\n    main = fonts/main.font\n}\naudio {\n    theme = audio/theme.mp3\n}\n").unwrap();
+
+        let resources=Resources::Directory(directory.clone());
+        match Manifest::load(&resources,"manifest.txt"){
+            Err(ManifestError::MissingFile{name,path})=>{
+                assert_eq!(name,"theme");
+                assert_eq!(path,directory.join("audio/theme.mp3"));
+            }
+            result=>panic!("ожидалась ошибка отсутствия файла, а не {:?}",result),
+        }
+
+        write(directory.join("manifest.txt"),"fonts {\n    main = fonts/main.font\n}\n").unwrap();
+        assert_eq!(Manifest::load(&resources,"manifest.txt").unwrap().fonts().len(),1);
+
+        remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+//! Длительность mp3 по заголовкам кадров (MPEG Layer III).
+//! `AudioWrapper` не сообщает, играет ли трек, поэтому время
+//! окончания озвучки считается по её длительности.
+
+use std::time::Duration;
+
+// Битрейты в кбит/с по номеру из заголовка
+const mpeg1_bitrates:[u32;15]=[0,32,40,48,56,64,80,96,112,128,160,192,224,256,320];
+const mpeg2_bitrates:[u32;15]=[0,8,16,24,32,40,48,56,64,80,96,112,128,144,160];
+
+const mpeg1_sample_rates:[u32;3]=[44100,48000,32000];
+
+/// Заголовок кадра.
+struct FrameHeader{
+    sample_rate:u32,
+    // Отсчётов в кадре
+    samples:u32,
+    // Длина кадра в байтах вместе с заголовком
+    length:usize,
+}
+
+impl FrameHeader{
+    fn parse(bytes:&[u8])->Option<FrameHeader>{
+        if bytes.len()<4 || bytes[0]!=0xFF || bytes[1]&0xE0!=0xE0{
+            return None
+        }
+
+        // 3 - MPEG 1, 2 - MPEG 2, 0 - MPEG 2.5
+        let version=(bytes[1]>>3)&3;
+        // 1 - Layer III
+        let layer=(bytes[1]>>1)&3;
+        if version==1 || layer!=1{
+            return None
+        }
+
+        let bitrate_index=(bytes[2]>>4) as usize;
+        let sample_rate_index=((bytes[2]>>2)&3) as usize;
+        // Свободный битрейт не поддерживается
+        if bitrate_index==0 || bitrate_index==15 || sample_rate_index==3{
+            return None
+        }
+        let padding=((bytes[2]>>1)&1) as usize;
+
+        let (bitrate,sample_rate,samples)=match version{
+            3=>(mpeg1_bitrates[bitrate_index],mpeg1_sample_rates[sample_rate_index],1152),
+            2=>(mpeg2_bitrates[bitrate_index],mpeg1_sample_rates[sample_rate_index]/2,576),
+            _=>(mpeg2_bitrates[bitrate_index],mpeg1_sample_rates[sample_rate_index]/4,576),
+        };
+
+        let length=(samples/8*bitrate*1000/sample_rate) as usize+padding;
+
+        Some(Self{
+            sample_rate,
+            samples,
+            length,
+        })
+    }
+}
+
+/// Длительность звука по всем кадрам файла.
+/// `None`, если в данных нет ни одного кадра.
+pub fn duration(data:&[u8])->Option<Duration>{
+    let mut position=id3_length(data);
+    let mut seconds=0f64;
+    let mut frames=0usize;
+
+    while position+4<=data.len(){
+        match FrameHeader::parse(&data[position..]){
+            // Неполный последний кадр не учитывается
+            Some(frame) if position+frame.length<=data.len()=>{
+                seconds+=frame.samples as f64/frame.sample_rate as f64;
+                frames+=1;
+                position+=frame.length;
+            }
+            Some(_)=>break,
+            // Поиск следующего заголовка (мусор, тег ID3v1 в конце)
+            None=>position+=1,
+        }
+    }
+
+    if frames==0{
+        None
+    }
+    else{
+        Some(Duration::from_secs_f64(seconds))
+    }
+}
+
+/// Длина тега ID3v2 в начале файла.
+fn id3_length(data:&[u8])->usize{
+    if data.len()<10 || &data[..3]!=b"ID3"{
+        return 0
+    }
+
+    // Размер записан по 7 бит в байте
+    let size=data[6..10].iter().fold(0usize,|size,&byte|(size<<7)|(byte&0x7F) as usize);
+    // Флаг нижнего колонтитула
+    let footer=if data[5]&0x10!=0{10}else{0};
+
+    10+size+footer
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // MPEG 1 Layer III, 128 кбит/с, 44100 Гц: 417 байт, с дополнением - 418
+    fn frame(padding:bool)->Vec<u8>{
+        let mut frame=vec![0xFF,0xFB,0x90,0x00];
+        if padding{
+            frame[2]|=2;
+        }
+        frame.resize(if padding{418}else{417},0);
+        frame
+    }
+
+    fn seconds(frames:usize)->f64{
+        frames as f64*1152f64/44100f64
+    }
+
+    #[test]
+    fn frames(){
+        let mut data=Vec::new();
+        for n in 0..100{
+            data.extend(frame(n%3==0));
+        }
+
+        let duration=duration(&data).unwrap();
+        assert!((duration.as_secs_f64()-seconds(100)).abs()<1e-6);
+    }
+
+    #[test]
+    fn tags(){
+        // ID3v2 размером 200 байт (1*128+72), ID3v1 в конце
+        let mut data=b"ID3\x04\x00\x00\x00\x00\x01\x48".to_vec();
+        data.resize(210,0xFF);
+        for _ in 0..10{
+            data.extend(frame(false));
+        }
+        data.extend(b"TAG");
+        data.resize(data.len()+125,0);
+
+        let duration=duration(&data).unwrap();
+        assert!((duration.as_secs_f64()-seconds(10)).abs()<1e-6);
+    }
+
+    #[test]
+    fn truncated(){
+        let mut data=frame(false);
+        data.extend(&frame(false)[..100]);
+
+        let duration=duration(&data).unwrap();
+        assert!((duration.as_secs_f64()-seconds(1)).abs()<1e-6);
+    }
+
+    #[test]
+    fn not_mp3(){
+        assert_eq!(duration(b""),None);
+        assert_eq!(duration(&[0u8;1000]),None);
+        // Layer I
+        assert_eq!(duration(&[0xFF,0xFF,0x90,0x00,0,0,0,0]),None);
+    }
+}
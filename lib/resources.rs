@@ -0,0 +1,707 @@
+//! Ресурсы игры: папка `./resources` или собранный из неё архив.
+//!
+//! Файлы называются путями относительно корня ресурсов через `/`
+//! (например, `images/characters/Лера.png`), так что остальной код не зависит от того,
+//! читаются ли ресурсы из папки или из архива.
+//!
+//! Формат архива (числа big-endian, см. `binary`):
+//! ```text
+//! "VNPK" версия:u16 длина_оглавления:u32
+//! оглавление: количество:u32, для каждого файла -
+//!     название:str сжатие:u8 смещение:u64 размер:u64 исходный_размер:u64 crc32:u32
+//! данные файлов подряд (смещения отсчитываются от конца оглавления)
+//! ```
+
+use crate::binary::{
+    BinaryError,
+    BinaryWriter,
+    BinaryReader,
+    crc32,
+};
+
+use std::{
+    fmt,
+    io::{self,Read,Seek,SeekFrom},
+    fs::{File,read,read_dir,create_dir_all,write},
+    collections::BTreeMap,
+    path::{Path,PathBuf},
+};
+
+const pack_magic:&[u8;4]=b"VNPK";
+const pack_version:u16=1;
+
+/// Размер заголовка до оглавления.
+const header_size:u64=10;
+
+/// Папка для распакованных файлов, которые читаются только по пути (шрифты, музыка).
+const unpacked_directory:&str="visual_novel_resources";
+
+/// Способ хранения файла в архиве.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Compression{
+    None,
+    /// LZSS (см. `compress`)
+    Lzss,
+}
+
+impl Compression{
+    fn from_u8(value:u8)->Option<Compression>{
+        match value{
+            0=>Some(Compression::None),
+            1=>Some(Compression::Lzss),
+            _=>None,
+        }
+    }
+
+    fn as_u8(self)->u8{
+        match self{
+            Compression::None=>0,
+            Compression::Lzss=>1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PackError{
+    Io(io::Error),
+    /// Файл не является архивом ресурсов.
+    InvalidMagic,
+    UnsupportedVersion(u16),
+    /// Оглавление повреждено.
+    InvalidIndex(BinaryError),
+    UnknownCompression{
+        name:String,
+        compression:u8,
+    },
+    /// Данные файла не совпадают с контрольной суммой или не распаковываются.
+    Corrupted(String),
+    NotFound(String),
+}
+
+impl fmt::Display for PackError{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            PackError::Io(e)=>e.fmt(f),
+            PackError::InvalidMagic=>write!(f,"файл не является архивом ресурсов"),
+            PackError::UnsupportedVersion(version)=>write!(f,"неподдерживаемая версия архива: {}",version),
+            PackError::InvalidIndex(e)=>write!(f,"оглавление архива повреждено: {}",e),
+            PackError::UnknownCompression{name,compression}=>write!(f,"{}: неизвестный способ сжатия {}",name,compression),
+            PackError::Corrupted(name)=>write!(f,"{}: данные повреждены",name),
+            PackError::NotFound(name)=>write!(f,"{}: нет в архиве",name),
+        }
+    }
+}
+
+impl std::error::Error for PackError{}
+
+impl From<io::Error> for PackError{
+    fn from(error:io::Error)->PackError{
+        PackError::Io(error)
+    }
+}
+
+impl From<BinaryError> for PackError{
+    fn from(error:BinaryError)->PackError{
+        PackError::InvalidIndex(error)
+    }
+}
+
+impl From<PackError> for io::Error{
+    fn from(error:PackError)->io::Error{
+        match error{
+            PackError::Io(error)=>error,
+            PackError::NotFound(_)=>io::Error::new(io::ErrorKind::NotFound,error),
+            _=>io::Error::new(io::ErrorKind::InvalidData,error),
+        }
+    }
+}
+
+#[derive(Clone,Debug,PartialEq)]
+struct PackEntry{
+    compression:Compression,
+    offset:u64,
+    size:u64,
+    original_size:u64,
+    crc:u32,
+}
+
+/// Архив ресурсов: оглавление читается при открытии,
+/// данные файлов - при обращении к ним.
+#[derive(Clone,Debug)]
+pub struct ResourcePack{
+    path:PathBuf,
+    entries:BTreeMap<String,PackEntry>,
+}
+
+impl ResourcePack{
+    pub fn open<P:AsRef<Path>>(path:P)->Result<ResourcePack,PackError>{
+        let path=path.as_ref();
+        let mut file=File::open(path)?;
+        let file_len=file.metadata()?.len();
+
+        let mut header=[0u8;header_size as usize];
+        file.read_exact(&mut header)?;
+
+        let mut reader=BinaryReader::new(&header);
+        if reader.read_raw(4)?!=pack_magic{
+            return Err(PackError::InvalidMagic)
+        }
+        let version=reader.read_u16()?;
+        if version!=pack_version{
+            return Err(PackError::UnsupportedVersion(version))
+        }
+        let index_len=reader.read_u32()?;
+
+        // Длины из файла не больше его размера
+        let data_start=header_size+index_len as u64;
+        if data_start>file_len{
+            return Err(PackError::InvalidIndex(BinaryError::UnexpectedEnd))
+        }
+
+        let mut index=vec![0u8;index_len as usize];
+        file.read_exact(&mut index)?;
+
+        let mut reader=BinaryReader::new(&index);
+        let count=reader.read_u32()?;
+        let mut entries=BTreeMap::new();
+
+        for _ in 0..count{
+            let name=reader.read_string()?;
+            let compression=reader.read_u8()?;
+            let compression=match Compression::from_u8(compression){
+                Some(compression)=>compression,
+                None=>return Err(PackError::UnknownCompression{name,compression}),
+            };
+
+            let entry=PackEntry{
+                compression,
+                offset:data_start.saturating_add(reader.read_u64()?),
+                size:reader.read_u64()?,
+                original_size:reader.read_u64()?,
+                crc:reader.read_u32()?,
+            };
+
+            let in_file=entry.offset.checked_add(entry.size).is_some_and(|end|end<=file_len);
+            let max_size=match compression{
+                Compression::None=>entry.size,
+                Compression::Lzss=>entry.size.saturating_mul(max_expansion as u64),
+            };
+            if !in_file || entry.original_size>max_size{
+                return Err(PackError::Corrupted(name))
+            }
+
+            entries.insert(name,entry);
+        }
+
+        Ok(Self{
+            path:path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn path(&self)->&Path{
+        &self.path
+    }
+
+    pub fn contains(&self,name:&str)->bool{
+        self.entries.contains_key(name)
+    }
+
+    /// Названия файлов по алфавиту.
+    pub fn names(&self)->impl Iterator<Item=&str>{
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Чтение и проверка файла.
+    pub fn read(&self,name:&str)->Result<Vec<u8>,PackError>{
+        let entry=match self.entries.get(name){
+            Some(entry)=>entry,
+            None=>return Err(PackError::NotFound(name.to_string())),
+        };
+
+        let mut file=File::open(&self.path)?;
+        file.seek(SeekFrom::Start(entry.offset))?;
+
+        // Файл мог измениться после открытия
+        let mut data=Vec::new();
+        file.take(entry.size).read_to_end(&mut data)?;
+        if data.len() as u64!=entry.size{
+            return Err(PackError::Corrupted(name.to_string()))
+        }
+
+        let data=match entry.compression{
+            Compression::None=>data,
+            Compression::Lzss=>match decompress(&data,entry.original_size as usize){
+                Some(data)=>data,
+                None=>return Err(PackError::Corrupted(name.to_string())),
+            },
+        };
+
+        if data.len() as u64!=entry.original_size || crc32(&data)!=entry.crc{
+            return Err(PackError::Corrupted(name.to_string()))
+        }
+
+        Ok(data)
+    }
+}
+
+/// Сборка архива в памяти.
+pub struct PackBuilder{
+    // Название, способ хранения, исходный размер, контрольная сумма и хранимые данные
+    files:Vec<(String,Compression,u64,u32,Vec<u8>)>,
+}
+
+impl PackBuilder{
+    pub fn new()->PackBuilder{
+        Self{
+            files:Vec::new(),
+        }
+    }
+
+    /// Добавление файла. При `compress` файл сжимается, если это уменьшает его размер.
+    /// Возвращает выбранный способ хранения.
+    pub fn add(&mut self,name:&str,data:Vec<u8>,compress:bool)->Compression{
+        let original_size=data.len() as u64;
+        let crc=crc32(&data);
+
+        let (compression,data)=if compress{
+            let compressed=self::compress(&data);
+            if compressed.len()<data.len(){
+                (Compression::Lzss,compressed)
+            }
+            else{
+                (Compression::None,data)
+            }
+        }
+        else{
+            (Compression::None,data)
+        };
+
+        self.files.push((name.to_string(),compression,original_size,crc,data));
+        compression
+    }
+
+    pub fn into_bytes(self)->Vec<u8>{
+        let mut index=BinaryWriter::new();
+        index.write_u32(self.files.len() as u32);
+
+        let mut offset=0u64;
+        for (name,compression,original_size,crc,data) in &self.files{
+            index.write_str(name);
+            index.write_u8(compression.as_u8());
+            index.write_u64(offset);
+            index.write_u64(data.len() as u64);
+            index.write_u64(*original_size);
+            index.write_u32(*crc);
+            offset+=data.len() as u64;
+        }
+
+        let index=index.into_bytes();
+
+        let mut pack=BinaryWriter::new();
+        pack.write_raw(pack_magic);
+        pack.write_u16(pack_version);
+        pack.write_bytes(&index);
+        for (_,_,_,_,data) in &self.files{
+            pack.write_raw(data);
+        }
+
+        pack.into_bytes()
+    }
+}
+
+impl Default for PackBuilder{
+    fn default()->PackBuilder{
+        PackBuilder::new()
+    }
+}
+
+/// Источник ресурсов.
+#[derive(Clone,Debug)]
+pub enum Resources{
+    /// Папка ресурсов
+    Directory(PathBuf),
+    /// Архив, собранный из папки ресурсов
+    Pack(ResourcePack),
+}
+
+impl Resources{
+    /// Архив `pack`, если он есть, иначе папка `directory`.
+    pub fn open<D:AsRef<Path>,P:AsRef<Path>>(directory:D,pack:P)->Result<Resources,PackError>{
+        let pack=pack.as_ref();
+        if pack.is_file(){
+            Ok(Resources::Pack(ResourcePack::open(pack)?))
+        }
+        else{
+            Ok(Resources::Directory(directory.as_ref().to_path_buf()))
+        }
+    }
+
+    /// Путь файла для сообщений об ошибках.
+    pub fn path(&self,name:&str)->PathBuf{
+        match self{
+            Resources::Directory(directory)=>directory.join(name),
+            Resources::Pack(pack)=>pack.path().join(name),
+        }
+    }
+
+    pub fn exists(&self,name:&str)->bool{
+        match self{
+            Resources::Directory(directory)=>directory.join(name).is_file(),
+            Resources::Pack(pack)=>pack.contains(name),
+        }
+    }
+
+    pub fn read(&self,name:&str)->io::Result<Vec<u8>>{
+        match self{
+            Resources::Directory(directory)=>read(directory.join(name)),
+            Resources::Pack(pack)=>Ok(pack.read(name)?),
+        }
+    }
+
+    pub fn read_to_string(&self,name:&str)->io::Result<String>{
+        String::from_utf8(self.read(name)?).map_err(|e|io::Error::new(io::ErrorKind::InvalidData,e))
+    }
+
+    /// Названия файлов папки `directory` без вложенных папок.
+    pub fn list(&self,directory:&str)->io::Result<Vec<String>>{
+        match self{
+            Resources::Directory(root)=>{
+                let mut files=Vec::new();
+                for entry in read_dir(root.join(directory))?{
+                    let entry=entry?;
+                    if entry.path().is_file(){
+                        if let Ok(name)=entry.file_name().into_string(){
+                            files.push(name);
+                        }
+                    }
+                }
+                Ok(files)
+            }
+            Resources::Pack(pack)=>{
+                let prefix=join(directory,"");
+                Ok(pack.names()
+                        .filter_map(|name|name.strip_prefix(&prefix))
+                        .filter(|name|!name.contains('/'))
+                        .map(str::to_string)
+                        .collect())
+            }
+        }
+    }
+
+    /// Путь к файлу на диске для загрузчиков, читающих только файлы.
+    ///
+    /// Файлы архива распаковываются во временную папку под латинскими названиями.
+    pub fn local_path(&self,name:&str)->io::Result<PathBuf>{
+        match self{
+            Resources::Directory(directory)=>Ok(directory.join(name)),
+            Resources::Pack(pack)=>{
+                let data=pack.read(name)?;
+
+                let directory=std::env::temp_dir().join(unpacked_directory);
+                create_dir_all(&directory)?;
+
+                let extension=Path::new(name).extension()
+                        .and_then(|extension|extension.to_str())
+                        .map(|extension|format!(".{}",extension))
+                        .unwrap_or_default();
+                let path=directory.join(format!("{:08x}{}",crc32(name.as_bytes()),extension));
+
+                write(&path,data)?;
+                Ok(path)
+            }
+        }
+    }
+}
+
+/// Путь `name` в папке ресурсов `directory`.
+pub fn join(directory:&str,name:&str)->String{
+    if directory.is_empty(){
+        name.to_string()
+    }
+    else{
+        format!("{}/{}",directory.trim_end_matches('/'),name)
+    }
+}
+
+// Сжатие LZSS: управляющий байт на каждые 8 элементов (бит 1 - повтор, 0 - байт как есть),
+// повтор - 2 байта: смещение назад (12 бит, 1..=4096) и длина (4 бита, 3..=18).
+const window_size:usize=4096;
+const min_match:usize=3;
+const max_match:usize=18;
+const hash_size:usize=1<<12;
+const max_chain:usize=64;
+/// Наибольшее увеличение при распаковке: повтор из 2 байтов даёт до 18.
+const max_expansion:usize=max_match/2;
+
+pub fn compress(data:&[u8])->Vec<u8>{
+    let mut output=Vec::with_capacity(data.len()/2);
+
+    // Последние позиции трёхбайтовых последовательностей по хешу и цепочки предыдущих
+    let mut head=vec![usize::MAX;hash_size];
+    let mut previous=vec![usize::MAX;data.len()];
+
+    let hash=|position:usize|{
+        let value=(data[position] as usize)<<8^(data[position+1] as usize)<<4^data[position+2] as usize;
+        value.wrapping_mul(2654435761)>>4&(hash_size-1)
+    };
+
+    let insert=|position:usize,head:&mut Vec<usize>,previous:&mut Vec<usize>|{
+        if position+min_match<=data.len(){
+            let h=hash(position);
+            previous[position]=head[h];
+            head[h]=position;
+        }
+    };
+
+    let mut position=0;
+    let mut control_position=0;
+    let mut flag=8;
+
+    while position<data.len(){
+        if flag==8{
+            control_position=output.len();
+            output.push(0u8);
+            flag=0;
+        }
+
+        // Поиск самого длинного повтора в окне
+        let mut best_len=0;
+        let mut best_offset=0;
+        if position+min_match<=data.len(){
+            let limit=max_match.min(data.len()-position);
+            let mut candidate=head[hash(position)];
+            let mut chain=0;
+            while candidate!=usize::MAX && position-candidate<=window_size && chain<max_chain{
+                let len=(0..limit).take_while(|&i|data[candidate+i]==data[position+i]).count();
+                if len>best_len{
+                    best_len=len;
+                    best_offset=position-candidate;
+                    if len==limit{
+                        break
+                    }
+                }
+                candidate=previous[candidate];
+                chain+=1;
+            }
+        }
+
+        if best_len>=min_match{
+            output[control_position]|=1<<flag;
+            let token=((best_offset-1)<<4|(best_len-min_match)) as u16;
+            output.extend_from_slice(&token.to_be_bytes());
+            for i in 0..best_len{
+                insert(position+i,&mut head,&mut previous);
+            }
+            position+=best_len;
+        }
+        else{
+            output.push(data[position]);
+            insert(position,&mut head,&mut previous);
+            position+=1;
+        }
+
+        flag+=1;
+    }
+
+    output
+}
+
+/// `None`, если данные повреждены или распаковываются больше `original_size` байтов.
+pub fn decompress(data:&[u8],original_size:usize)->Option<Vec<u8>>{
+    let mut output=Vec::with_capacity(original_size.min(data.len().saturating_mul(max_expansion)));
+    let mut position=0;
+
+    while position<data.len(){
+        let control=data[position];
+        position+=1;
+
+        for flag in 0..8{
+            if position==data.len(){
+                break
+            }
+
+            if control&(1<<flag)==0{
+                output.push(data[position]);
+                position+=1;
+            }
+            else{
+                let token=u16::from_be_bytes([data[position],*data.get(position+1)?]) as usize;
+                position+=2;
+
+                let offset=(token>>4)+1;
+                let len=(token&0xF)+min_match;
+                if offset>output.len(){
+                    return None
+                }
+
+                let start=output.len()-offset;
+                for i in 0..len{
+                    let byte=output[start+i];
+                    output.push(byte);
+                }
+            }
+
+            if output.len()>original_size{
+                return None
+            }
+        }
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use std::fs::remove_file;
+
+    /// Псевдослучайные несжимаемые данные.
+    fn noise(len:usize)->Vec<u8>{
+        let mut state=0x2545F491u32;
+        (0..len).map(|_|{
+            state^=state<<13;
+            state^=state>>17;
+            state^=state<<5;
+            state as u8
+        }).collect()
+    }
+
+    fn text()->Vec<u8>{
+        "А - Привет! Привет! Привет, как дела?\n".repeat(200).into_bytes()
+    }
+
+    /// Архив во временной папке, удаляемый после теста.
+    struct TempPack{
+        path:PathBuf,
+    }
+
+    impl TempPack{
+        fn new(name:&str,bytes:&[u8])->TempPack{
+            let path=std::env::temp_dir().join(format!("visual_novel_test_{}_{}.pack",name,std::process::id()));
+            write(&path,bytes).unwrap();
+            Self{
+                path,
+            }
+        }
+    }
+
+    impl Drop for TempPack{
+        fn drop(&mut self){
+            remove_file(&self.path);
+        }
+    }
+
+    fn pack_bytes()->Vec<u8>{
+        let mut builder=PackBuilder::new();
+        assert_eq!(builder.add("dialogues/intro.txt",text(),true),Compression::Lzss);
+        assert_eq!(builder.add("images/noise.png",noise(1000),true),Compression::None);
+        assert_eq!(builder.add("empty.txt",Vec::new(),true),Compression::None);
+        assert_eq!(builder.add("page_table.txt",text(),false),Compression::None);
+        builder.into_bytes()
+    }
+
+    #[test]
+    fn lzss_round_trip(){
+        for data in [Vec::new(),b"a".to_vec(),b"abcabcabcabcabc".to_vec(),vec![0u8;100000],text(),noise(10000)]{
+            let compressed=compress(&data);
+            assert_eq!(decompress(&compressed,data.len()),Some(data.clone()));
+        }
+
+        assert!(compress(&text()).len()<text().len()/4);
+    }
+
+    #[test]
+    fn lzss_corrupted(){
+        let compressed=compress(&text());
+
+        // Распаковка больше заявленного размера
+        assert_eq!(decompress(&compressed,100),None);
+        // Повтор до начала данных
+        assert_eq!(decompress(&[1,0xFF,0xF0],usize::MAX),None);
+        // Обрезанный повтор
+        assert_eq!(decompress(&[2,b'a',0x00],usize::MAX),None);
+    }
+
+    #[test]
+    fn pack_round_trip(){
+        let pack=TempPack::new("round_trip",&pack_bytes());
+        let resources=Resources::open("./missing_directory",&pack.path).unwrap();
+
+        assert_eq!(resources.read("dialogues/intro.txt").unwrap(),text());
+        assert_eq!(resources.read("images/noise.png").unwrap(),noise(1000));
+        assert_eq!(resources.read("empty.txt").unwrap(),Vec::<u8>::new());
+        assert_eq!(resources.read_to_string("page_table.txt").unwrap().into_bytes(),text());
+
+        assert!(resources.exists("images/noise.png"));
+        assert!(!resources.exists("images"));
+        assert_eq!(resources.read("missing.txt").unwrap_err().kind(),io::ErrorKind::NotFound);
+
+        let mut files=resources.list("images").unwrap();
+        files.sort();
+        assert_eq!(files,vec!["noise.png".to_string()]);
+        assert_eq!(resources.list("").unwrap().len(),2);
+    }
+
+    #[test]
+    fn corrupted_data(){
+        let mut bytes=pack_bytes();
+        let last=bytes.len()-1;
+        bytes[last]^=1;
+
+        let pack=TempPack::new("corrupted_data",&bytes);
+        let resources=ResourcePack::open(&pack.path).unwrap();
+
+        assert!(matches!(resources.read("page_table.txt"),Err(PackError::Corrupted(_))));
+        assert!(resources.read("images/noise.png").is_ok());
+    }
+
+    #[test]
+    fn truncated_pack(){
+        let bytes=pack_bytes();
+
+        for len in [0,5,header_size as usize,header_size as usize+20,bytes.len()-1]{
+            let pack=TempPack::new(&format!("truncated_{}",len),&bytes[..len]);
+            assert!(ResourcePack::open(&pack.path).is_err(),"длина {}",len);
+        }
+    }
+
+    /// Архив из одного сжатого файла `a` с четырьмя байтами данных и заданными размерами.
+    fn raw_pack(size:u64,original_size:u64)->Vec<u8>{
+        let mut index=BinaryWriter::new();
+        index.write_u32(1);
+        index.write_str("a");
+        index.write_u8(Compression::Lzss.as_u8());
+        index.write_u64(0);
+        index.write_u64(size);
+        index.write_u64(original_size);
+        index.write_u32(0);
+
+        let mut bytes=BinaryWriter::new();
+        bytes.write_raw(pack_magic);
+        bytes.write_u16(pack_version);
+        bytes.write_bytes(index.as_bytes());
+        bytes.write_raw(&[0u8;4]);
+        bytes.into_bytes()
+    }
+
+    #[test]
+    fn oversized_lengths(){
+        let open=|name:&str,bytes:&[u8]|ResourcePack::open(&TempPack::new(name,bytes).path);
+
+        assert!(open("valid",&raw_pack(4,36)).is_ok());
+
+        // Исходный размер больше возможного
+        assert!(matches!(open("original_size",&raw_pack(4,u64::MAX)),Err(PackError::Corrupted(_))));
+
+        // Данные за концом файла
+        assert!(matches!(open("size",&raw_pack(5,4)),Err(PackError::Corrupted(_))));
+        assert!(matches!(open("size_overflow",&raw_pack(u64::MAX,4)),Err(PackError::Corrupted(_))));
+
+        // Оглавление длиной 4 ГиБ
+        let mut bytes=raw_pack(4,4);
+        bytes[6..10].copy_from_slice(&[0xFF;4]);
+        assert!(matches!(open("index_len",&bytes),Err(PackError::InvalidIndex(_))));
+    }
+}
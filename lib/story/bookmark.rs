@@ -0,0 +1,232 @@
+use super::{
+    PageTable,
+    Dialogue,
+};
+
+use crate::binary::crc32;
+
+/// Место в истории для сохранений, переживающее правку таблицы страниц и диалогов.
+///
+/// Страница находится по названию, шаг диалога - по ближайшей метке `#`
+/// и смещению от неё или по хешу содержимого (`Dialogue::step_hash`).
+/// Номера страницы и шага используются, если ничего из этого не найдено.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Bookmark{
+    /// Название страницы, пустое - неизвестно (сохранения старых версий)
+    pub page_label:String,
+    /// Номер страницы на момент сохранения
+    pub page:usize,
+    /// Номер шага диалога на момент сохранения
+    pub line:usize,
+    /// Ближайшая метка на шаге или перед ним и количество шагов от неё
+    pub anchor:Option<(String,usize)>,
+    /// Хеш содержимого шага, `None` - неизвестен (сохранения старых версий)
+    pub hash:Option<u32>,
+}
+
+/// Найденное место закладки.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum Resolved{
+    /// Место не изменилось.
+    Exact(usize),
+    /// Место сдвинуто правкой, но найдено.
+    Moved(usize),
+    /// Место удалено, выбрано ближайшее к прежнему.
+    Nearest(usize),
+}
+
+impl Resolved{
+    pub fn index(self)->usize{
+        match self{
+            Resolved::Exact(index)|Resolved::Moved(index)|Resolved::Nearest(index)=>index,
+        }
+    }
+}
+
+impl Bookmark{
+    /// Начало истории.
+    pub const fn new()->Bookmark{
+        Self{
+            page_label:String::new(),
+            page:0,
+            line:0,
+            anchor:None,
+            hash:None,
+        }
+    }
+
+    /// Закладка на шаге `line` диалога `dialogue` страницы `page`.
+    /// `None`, если страницы нет в таблице.
+    pub fn at(page_table:&PageTable,page:usize,dialogue:&Dialogue,line:usize)->Option<Bookmark>{
+        Some(Self{
+            page_label:page_table.pages().get(page)?.label.clone(),
+            page,
+            line,
+            anchor:dialogue.anchor_before(line).map(|(anchor,step)|(anchor.to_string(),line-step)),
+            hash:dialogue.step_hash(line),
+        })
+    }
+
+    /// Ключ шага для отметки прочитанных строк:
+    /// хеши названия страницы и содержимого шага.
+    /// `None`, если содержимое неизвестно.
+    pub fn line_key(&self)->Option<u64>{
+        let hash=self.hash?;
+        Some((crc32(self.page_label.as_bytes()) as u64)<<32|hash as u64)
+    }
+
+    /// Поиск страницы по названию.
+    pub fn resolve_page(&self,page_table:&PageTable)->Resolved{
+        if self.page_label.is_empty(){
+            return Resolved::Exact(self.page)
+        }
+
+        match page_table.index_of(&self.page_label){
+            Some(page) if page==self.page=>Resolved::Exact(page),
+            Some(page)=>Resolved::Moved(page),
+            None=>Resolved::Nearest(self.page.min(page_table.len().saturating_sub(1))),
+        }
+    }
+
+    /// Поиск шага в диалоге найденной страницы:
+    /// по метке, затем ближайший к ожидаемому месту шаг с тем же содержимым.
+    pub fn resolve_line(&self,dialogue:&Dialogue)->Resolved{
+        let hash=match self.hash{
+            Some(hash)=>hash,
+            None=>return Resolved::Exact(self.line),
+        };
+
+        // Ожидаемое место: от метки или прежний номер
+        let expected=self.anchor.as_ref()
+                .and_then(|(anchor,offset)|dialogue.anchor(anchor).map(|step|step+offset))
+                .unwrap_or(self.line);
+
+        if dialogue.step_hash(expected)==Some(hash){
+            return if expected==self.line{
+                Resolved::Exact(expected)
+            }
+            else{
+                Resolved::Moved(expected)
+            }
+        }
+
+        let nearest=(0..dialogue.len())
+                .filter(|&line|dialogue.step_hash(line)==Some(hash))
+                .min_by_key(|&line|line.abs_diff(expected));
+
+        match nearest{
+            Some(line)=>Resolved::Moved(line),
+            None=>Resolved::Nearest(expected.min(dialogue.len().saturating_sub(1))),
+        }
+    }
+}
+
+impl Default for Bookmark{
+    fn default()->Bookmark{
+        Bookmark::new()
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use std::path::Path;
+
+    fn page_table(source:&str)->PageTable{
+        PageTable::parse(source,Path::new("page_table.txt")).unwrap()
+    }
+
+    fn dialogue(source:&str)->Dialogue{
+        Dialogue::parse(source,Path::new("test.txt")).unwrap()
+    }
+
+    const table:&str="\
+start {
+    wallpaper = w
+    dialogue = d
+}
+bridge {
+    wallpaper = w
+    dialogue = d
+}
+";
+
+    const steps:&str="{\n}\n_ - Раз\n_ - Два\n# мост\n_ - Три\n_ - Четыре\n";
+
+    /// Закладка на шаге `_ - Четыре` страницы `bridge`.
+    fn bookmark()->Bookmark{
+        Bookmark::at(&page_table(table),1,&dialogue(steps),3).unwrap()
+    }
+
+    #[test]
+    fn exact(){
+        let bookmark=bookmark();
+        assert_eq!(bookmark.page_label,"bridge");
+        assert_eq!(bookmark.anchor,Some(("мост".to_string(),1)));
+
+        assert_eq!(bookmark.resolve_page(&page_table(table)),Resolved::Exact(1));
+        assert_eq!(bookmark.resolve_line(&dialogue(steps)),Resolved::Exact(3));
+
+        assert_eq!(Bookmark::at(&page_table(table),2,&dialogue(steps),0),None);
+
+        // Сохранения старых версий без названия и хеша
+        let old=Bookmark{
+            page:1,
+            line:3,
+            ..Bookmark::new()
+        };
+        assert_eq!(old.resolve_page(&page_table("")),Resolved::Exact(1));
+        assert_eq!(old.resolve_line(&dialogue("{\n}\n")),Resolved::Exact(3));
+    }
+
+    #[test]
+    fn moved_after_insertion(){
+        let bookmark=bookmark();
+
+        let edited=format!("intro {{\n    wallpaper = w\n    dialogue = d\n}}\n{}",table);
+        assert_eq!(bookmark.resolve_page(&page_table(&edited)),Resolved::Moved(2));
+
+        let edited="{\n}\n_ - Ноль\n_ - Раз\n_ - Два\n# мост\n_ - Три\n_ - Четыре\n";
+        assert_eq!(bookmark.resolve_line(&dialogue(edited)),Resolved::Moved(4));
+    }
+
+    #[test]
+    fn anchor_offset(){
+        let bookmark=Bookmark::at(&page_table(table),0,&dialogue("{\n}\n_ - Эхо\n_ - Два\n# мост\n_ - Три\n_ - Эхо\n"),3).unwrap();
+
+        // Копия шага перед меткой ближе к прежнему номеру, но место берётся от метки
+        let edited=dialogue("{\n}\n_ - А\n_ - Б\n_ - Эхо\n_ - Два\n_ - В\n_ - Г\n# мост\n_ - Три\n_ - Эхо\n");
+        assert_eq!(bookmark.resolve_line(&edited),Resolved::Moved(7));
+
+        let without_anchor=Bookmark{
+            anchor:None,
+            ..bookmark
+        };
+        assert_eq!(without_anchor.resolve_line(&edited),Resolved::Moved(2));
+    }
+
+    #[test]
+    fn nearest_by_hash(){
+        let bookmark=bookmark();
+
+        // Метка удалена: ближайший к прежнему номеру шаг с тем же содержимым
+        let edited=dialogue("{\n}\n_ - Четыре\n_ - Раз\n_ - Два\n_ - Три\n_ - Пять\n_ - Четыре\n");
+        assert_eq!(bookmark.resolve_line(&edited),Resolved::Moved(5));
+
+        // Шаг удалён: ближайший к ожидаемому месту
+        let edited=dialogue("{\n}\n_ - Раз\n_ - Два\n# мост\n_ - Три\n");
+        assert_eq!(bookmark.resolve_line(&edited),Resolved::Nearest(2));
+    }
+
+    #[test]
+    fn deleted_page(){
+        let bookmark=bookmark();
+
+        let edited="start {\n    wallpaper = w\n    dialogue = d\n}\n";
+        assert_eq!(bookmark.resolve_page(&page_table(edited)),Resolved::Nearest(0));
+
+        let edited=format!("{}river {{\n    wallpaper = w\n    dialogue = d\n}}\n",edited);
+        assert_eq!(bookmark.resolve_page(&page_table(&edited)),Resolved::Nearest(1));
+    }
+}
@@ -0,0 +1,766 @@
+use super::{
+    StoryError,
+    ParseError,
+    ParseErrorKind,
+    PageTable,
+    Declarations,
+    Condition,
+    Assignment,
+    TextTemplate,
+    indent,
+};
+
+use crate::binary::crc32;
+
+use std::{
+    fs::read_to_string,
+    collections::HashMap,
+    path::Path,
+};
+
+/// Наибольшее число персонажей в заголовке диалога
+/// (все они одновременно выводятся на экран).
+pub const max_characters:usize=8;
+
+/// Положение персонажа на экране (слева направо).
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,PartialOrd,Ord,Hash)]
+pub enum Position{
+    /// Слева с краю
+    Left,
+    /// Центр левой половины
+    LeftCenter,
+    /// Левее центра
+    CenterLeft,
+    /// Центр
+    #[default]
+    Center,
+    /// Правее центра
+    CenterRight,
+    /// Центр правой половины
+    RightCenter,
+    /// Справа с краю
+    Right,
+}
+
+impl Position{
+    pub const all:[Position;7]=[
+        Position::Left,
+        Position::LeftCenter,
+        Position::CenterLeft,
+        Position::Center,
+        Position::CenterRight,
+        Position::RightCenter,
+        Position::Right,
+    ];
+
+    pub fn parse(name:&str)->Option<Position>{
+        Position::all.iter().copied().find(|position|position.name()==name)
+    }
+
+    pub fn name(&self)->&'static str{
+        match self{
+            Position::Left=>"Left",
+            Position::LeftCenter=>"LeftCenter",
+            Position::CenterLeft=>"CenterLeft",
+            Position::Center=>"Center",
+            Position::CenterRight=>"CenterRight",
+            Position::RightCenter=>"RightCenter",
+            Position::Right=>"Right",
+        }
+    }
+}
+
+/// Персонаж, объявленный в заголовке диалога.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Character{
+    /// Краткое имя, используемое в строках диалога
+    pub short_name:String,
+    /// Полное имя
+    pub name:String,
+    /// Дополнительная черта (выбор текстуры персонажа)
+    pub feature:Option<String>,
+    pub position:Position,
+}
+
+impl Character{
+    /// Название файла текстуры без расширения: `[имя]` или `[имя].[черта]`.
+    pub fn texture_name(&self)->String{
+        match &self.feature{
+            Some(feature)=>format!("{}.{}",self.name,feature),
+            None=>self.name.clone(),
+        }
+    }
+}
+
+/// Кто произносит строку.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Speaker{
+    /// `_` - без имени (мысли, предыстория)
+    Narrator,
+    /// `{}` - игрок
+    Player,
+    /// Номер персонажа в заголовке
+    Character(usize),
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct Line{
+    pub speaker:Speaker,
+    /// Текст без подстановок (см. `TextTemplate`)
+    pub text:String,
+    pub condition:Option<Condition>,
+}
+
+/// Вариант выбора игрока.
+#[derive(Clone,Debug,PartialEq)]
+pub struct ChoiceOption{
+    /// Текст без подстановок (см. `TextTemplate`)
+    pub text:String,
+    /// Название страницы для перехода,
+    /// `None` - продолжение текущего диалога
+    pub target:Option<String>,
+    /// Условие показа варианта
+    pub condition:Option<Condition>,
+    /// Присваивания при выборе варианта
+    pub effects:Vec<Assignment>,
+    /// Номер строки варианта в файле
+    pub line:usize,
+    /// Номер символа, с которого начинается название страницы
+    pub column:usize,
+}
+
+/// Выбор из идущих подряд вариантов.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Choice{
+    pub options:Vec<ChoiceOption>,
+}
+
+/// Шаг диалога.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Step{
+    Line(Line),
+    Choice(Choice),
+    /// `set [имя] = [выражение]`
+    Set{
+        assignment:Assignment,
+        condition:Option<Condition>,
+    },
+}
+
+/// Диалог (`./resources/dialogues/[название].txt`).
+///
+/// ```text
+/// {
+///     [краткое имя] = [полное имя].[черта] (положение)
+/// }
+/// [краткое имя] - [текст]
+/// _ - [текст без имени]
+/// {} - [текст игрока]
+/// * [вариант выбора] -> [название страницы]
+/// * [вариант выбора без перехода]
+/// * [вариант выбора] -> [название страницы] | [имя] = [выражение], [имя] = [выражение]
+/// set [имя] = [выражение]
+/// # [метка]
+/// ```
+/// Перед строкой, присваиванием или текстом варианта выбора
+/// может стоять условие `if [выражение]:`.
+///
+/// Метка `#` отмечает следующий шаг, чтобы сохранения
+/// находили своё место после правки диалога (см. `Bookmark`).
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Dialogue{
+    characters:Vec<Character>,
+    steps:Vec<Step>,
+    // Название метки и номер отмеченного шага
+    anchors:Vec<(String,usize)>,
+}
+
+impl Dialogue{
+    /// Диалог без персонажей и шагов.
+    pub const empty:Dialogue=Dialogue{
+        characters:Vec::new(),
+        steps:Vec::new(),
+        anchors:Vec::new(),
+    };
+
+    pub fn load<P:AsRef<Path>>(path:P)->Result<Dialogue,StoryError>{
+        let path=path.as_ref();
+        let source=match read_to_string(path){
+            Ok(source)=>source,
+            Err(e)=>return Err(StoryError::Io(path.to_path_buf(),e)),
+        };
+
+        Ok(Dialogue::parse(&source,path)?)
+    }
+
+    /// `path` используется только для сообщений об ошибках.
+    pub fn parse(source:&str,path:&Path)->Result<Dialogue,ParseError>{
+        let mut lines=source.lines()
+                .enumerate()
+                .map(|(n,line)|(n+1,line.trim_start_matches('\u{feff}')))
+                .filter(|(_,line)|!line.trim().is_empty());
+
+        // Заголовок
+        let (header_line,header)=match lines.next(){
+            Some(line)=>line,
+            None=>return Err(ParseError::new(path,1,"",0,ParseErrorKind::ExpectedHeader)),
+        };
+
+        if header.trim()!="{"{
+            return Err(ParseError::new(path,header_line,header,indent(header),ParseErrorKind::ExpectedHeader))
+        }
+
+        let mut characters=Vec::new();
+        // Краткое имя и строка, в которой оно объявлено
+        let mut short_names:HashMap<String,(usize,usize)>=HashMap::new();
+
+        loop{
+            let (n,line)=match lines.next(){
+                Some(line)=>line,
+                None=>return Err(ParseError::new(path,header_line,header,indent(header),ParseErrorKind::UnclosedHeader)),
+            };
+
+            let trimmed=line.trim();
+            if trimmed=="}"{
+                break
+            }
+
+            let offset=indent(line);
+            let error=|kind|ParseError::new(path,n,line,offset,kind);
+
+            if characters.len()==max_characters{
+                return Err(error(ParseErrorKind::TooManyCharacters(max_characters)))
+            }
+
+            let (short_name,definition)=match trimmed.split_once('='){
+                Some((short_name,definition))=>(short_name.trim(),definition.trim()),
+                None=>return Err(error(ParseErrorKind::ExpectedCharacterDefinition)),
+            };
+
+            if short_name.is_empty() || definition.is_empty(){
+                return Err(error(ParseErrorKind::ExpectedCharacterDefinition))
+            }
+
+            if short_name=="_" || short_name=="{}"{
+                return Err(error(ParseErrorKind::ReservedShortName(short_name.to_string())))
+            }
+
+            if let Some(&(first_line,_))=short_names.get(short_name){
+                return Err(error(ParseErrorKind::DuplicateShortName{
+                    short_name:short_name.to_string(),
+                    first_line,
+                }))
+            }
+
+            // Положение на экране
+            let (full_name,position)=match definition.strip_suffix(')'){
+                Some(rest)=>match rest.rsplit_once('('){
+                    Some((full_name,position_name))=>match Position::parse(position_name.trim()){
+                        Some(position)=>(full_name.trim_end(),position),
+                        None=>{
+                            let offset=line.find(definition).unwrap()+rest.len()-position_name.len();
+                            let kind=ParseErrorKind::UnknownPosition(position_name.trim().to_string());
+                            return Err(ParseError::new(path,n,line,offset,kind))
+                        }
+                    }
+                    None=>return Err(error(ParseErrorKind::ExpectedCharacterDefinition)),
+                }
+                None=>(definition,Position::default()),
+            };
+
+            // Дополнительная черта
+            let (name,feature)=match full_name.rsplit_once('.'){
+                Some((name,feature))=>(name.trim(),Some(feature.trim().to_string())),
+                None=>(full_name,None),
+            };
+
+            if name.is_empty() || feature.as_ref().is_some_and(|feature|feature.is_empty()){
+                return Err(error(ParseErrorKind::ExpectedCharacterDefinition))
+            }
+
+            short_names.insert(short_name.to_string(),(n,characters.len()));
+            characters.push(Character{
+                short_name:short_name.to_string(),
+                name:name.to_string(),
+                feature,
+                position,
+            });
+        }
+
+        // Строки диалога
+        let mut steps=Vec::new();
+
+        let mut anchors:Vec<(String,usize)>=Vec::new();
+        // Строки, в которых поставлены метки
+        let mut anchor_lines:HashMap<String,usize>=HashMap::new();
+
+        for (n,line) in lines{
+            // Все части строки ниже - её окончания или их начала,
+            // что позволяет вычислять их положение в строке
+            let line=line.trim_end();
+            let trimmed=line.trim_start();
+            let offset=indent(line);
+
+            // Метка следующего шага
+            if let Some(name)=trimmed.strip_prefix('#'){
+                let name=name.trim();
+                if name.is_empty(){
+                    return Err(ParseError::new(path,n,line,offset,ParseErrorKind::EmptyAnchor))
+                }
+
+                if let Some(&first_line)=anchor_lines.get(name){
+                    let kind=ParseErrorKind::DuplicateAnchor{
+                        name:name.to_string(),
+                        first_line,
+                    };
+                    return Err(ParseError::new(path,n,line,suffix_offset(line,name),kind))
+                }
+
+                anchor_lines.insert(name.to_string(),n);
+                anchors.push((name.to_string(),steps.len()));
+                continue
+            }
+
+            // Вариант выбора
+            if let Some(option)=trimmed.strip_prefix('*'){
+                let (condition,option)=parse_condition(option,line,n,path)?;
+                let option_offset=suffix_offset(line,option);
+
+                // Присваивания при выборе
+                // (`|` в подстановках `{м|ж}` к ним не относится)
+                let (option,effects)=match split_outside_braces(option,'|'){
+                    Some((option,effects))=>(option,parse_effects(effects,line,n,path)?),
+                    None=>(option,Vec::new()),
+                };
+
+                let (text,target)=match option.split_once("->"){
+                    Some((text,target))=>(text.trim(),Some(target)),
+                    None=>(option.trim(),None),
+                };
+
+                if text.is_empty(){
+                    return Err(ParseError::new(path,n,line,offset,ParseErrorKind::EmptyChoiceText))
+                }
+
+                let (target,column_offset)=match target{
+                    Some(target)=>{
+                        // Начало названия: после текста и `->`
+                        let target_offset=option_offset+option.len()-target.len();
+                        let target_offset=target_offset+indent(target);
+                        let target=target.trim();
+                        if target.is_empty(){
+                            return Err(ParseError::new(path,n,line,target_offset,ParseErrorKind::EmptyChoiceTarget))
+                        }
+                        (Some(target.to_string()),target_offset)
+                    }
+                    None=>(None,offset),
+                };
+
+                check_text(text,line,n,path)?;
+
+                let option=ChoiceOption{
+                    text:text.to_string(),
+                    target,
+                    condition,
+                    effects,
+                    line:n,
+                    column:line[..column_offset].chars().count()+1,
+                };
+
+                // Идущие подряд варианты составляют один выбор,
+                // если между ними нет метки
+                let anchored=anchors.last().is_some_and(|&(_,step)|step==steps.len());
+                match steps.last_mut(){
+                    Some(Step::Choice(choice)) if !anchored=>choice.options.push(option),
+                    _=>steps.push(Step::Choice(Choice{options:vec![option]})),
+                }
+                continue
+            }
+
+            let (condition,rest)=parse_condition(trimmed,line,n,path)?;
+
+            // Присваивание
+            if let Some(assignment)=rest.strip_prefix("set "){
+                let column=line[..suffix_offset(line,assignment)].chars().count()+1;
+                steps.push(Step::Set{
+                    assignment:Assignment::parse(assignment,path,n,column)?,
+                    condition,
+                });
+                continue
+            }
+
+            let (short_name,text)=match rest.split_once(" - "){
+                Some((short_name,text))=>(short_name.trim(),text.trim()),
+                None=>return Err(ParseError::new(path,n,line,suffix_offset(line,rest),ParseErrorKind::ExpectedLineSeparator)),
+            };
+
+            let speaker=match short_name{
+                "_"=>Speaker::Narrator,
+                "{}"=>Speaker::Player,
+                _=>match short_names.get(short_name){
+                    Some(&(_,index))=>Speaker::Character(index),
+                    None=>{
+                        let kind=ParseErrorKind::UndefinedShortName(short_name.to_string());
+                        return Err(ParseError::new(path,n,line,suffix_offset(line,rest),kind))
+                    }
+                }
+            };
+
+            check_text(text,line,n,path)?;
+
+            steps.push(Step::Line(Line{
+                speaker,
+                text:text.to_string(),
+                condition,
+            }));
+        }
+
+        Ok(Self{
+            characters,
+            steps,
+            anchors,
+        })
+    }
+
+    /// Проверка, что все переходы ведут на существующие страницы.
+    /// `path` - путь файла диалога для сообщений об ошибках.
+    pub fn check_targets(&self,page_table:&PageTable,path:&Path)->Result<(),ParseError>{
+        for step in &self.steps{
+            if let Step::Choice(choice)=step{
+                for option in &choice.options{
+                    if let Some(target)=&option.target{
+                        if page_table.index_of(target).is_none(){
+                            return Err(ParseError{
+                                path:path.to_path_buf(),
+                                line:option.line,
+                                column:option.column,
+                                kind:ParseErrorKind::UnknownPage(target.clone()),
+                            })
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Проверка имён и типов переменных в условиях и присваиваниях.
+    /// `path` - путь файла диалога для сообщений об ошибках.
+    pub fn check_variables(&self,declarations:&Declarations,path:&Path)->Result<(),ParseError>{
+        for step in &self.steps{
+            match step{
+                Step::Line(line)=>if let Some(condition)=&line.condition{
+                    condition.check(declarations,path)?;
+                }
+
+                Step::Choice(choice)=>for option in &choice.options{
+                    if let Some(condition)=&option.condition{
+                        condition.check(declarations,path)?;
+                    }
+                    for effect in &option.effects{
+                        effect.check(declarations,path)?;
+                    }
+                }
+
+                Step::Set{assignment,condition}=>{
+                    if let Some(condition)=condition{
+                        condition.check(declarations,path)?;
+                    }
+                    assignment.check(declarations,path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Персонажи в порядке объявления.
+    pub fn characters(&self)->&[Character]{
+        &self.characters
+    }
+
+    pub fn steps(&self)->&[Step]{
+        &self.steps
+    }
+
+    pub fn len(&self)->usize{
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.steps.is_empty()
+    }
+
+    pub fn get(&self,index:usize)->Option<&Step>{
+        self.steps.get(index)
+    }
+
+    /// Номер шага, отмеченного меткой `name`.
+    pub fn anchor(&self,name:&str)->Option<usize>{
+        self.anchors.iter().find(|(anchor,_)|anchor==name).map(|&(_,step)|step)
+    }
+
+    /// Ближайшая метка на шаге `index` или перед ним и номер отмеченного шага.
+    pub fn anchor_before(&self,index:usize)->Option<(&str,usize)>{
+        self.anchors.iter()
+                .rev()
+                .find(|&&(_,step)|step<=index)
+                .map(|(anchor,step)|(anchor.as_str(),*step))
+    }
+
+    /// Хеш содержимого строки или выбора: говорящий и текст.
+    /// Не зависит от положения шага в диалоге.
+    /// `None` для присваиваний и номеров за концом диалога.
+    pub fn step_hash(&self,index:usize)->Option<u32>{
+        let content=match self.steps.get(index)?{
+            Step::Line(line)=>{
+                let speaker=match line.speaker{
+                    Speaker::Narrator=>"_",
+                    Speaker::Player=>"{}",
+                    Speaker::Character(_)=>self.character(line.speaker).unwrap().short_name.as_str(),
+                };
+                format!("{} - {}",speaker,line.text)
+            }
+            Step::Choice(choice)=>{
+                let texts:Vec<&str>=choice.options.iter().map(|option|option.text.as_str()).collect();
+                format!("* {}",texts.join("\n* "))
+            }
+            Step::Set{..}=>return None,
+        };
+
+        Some(crc32(content.as_bytes()))
+    }
+
+    /// Персонаж, произносящий строку (`None` для `_` и `{}`).
+    pub fn character(&self,speaker:Speaker)->Option<&Character>{
+        match speaker{
+            Speaker::Character(index)=>self.characters.get(index),
+            _=>None,
+        }
+    }
+}
+
+/// Смещение окончания `suffix` строки `line` в байтах.
+fn suffix_offset(line:&str,suffix:&str)->usize{
+    line.len()-suffix.len()
+}
+
+/// Разделение `text` по первому символу `separator` вне фигурных скобок.
+fn split_outside_braces(text:&str,separator:char)->Option<(&str,&str)>{
+    let mut depth=0usize;
+    for (i,c) in text.char_indices(){
+        match c{
+            '{'=>depth+=1,
+            '}'=>depth=depth.saturating_sub(1),
+            _ if c==separator && depth==0=>return Some((&text[..i],&text[i+c.len_utf8()..])),
+            _=>{}
+        }
+    }
+    None
+}
+
+/// Проверка подстановок в тексте `text` - части строки `line`.
+fn check_text(text:&str,line:&str,n:usize,path:&Path)->Result<(),ParseError>{
+    TextTemplate::new(text).check().map_err(|e|{
+        let offset=text.as_ptr() as usize-line.as_ptr() as usize+e.offset;
+        ParseError::new(path,n,line,offset,e.kind)
+    })
+}
+
+/// Разбор условия `if [выражение]:` в начале `rest` - окончания строки `line`.
+/// Возвращает условие и окончание строки после него.
+fn parse_condition<'a>(rest:&'a str,line:&str,n:usize,path:&Path)->Result<(Option<Condition>,&'a str),ParseError>{
+    let rest=rest.trim_start();
+
+    let condition=match rest.strip_prefix("if "){
+        Some(condition)=>condition.trim_start(),
+        None=>return Ok((None,rest)),
+    };
+
+    match condition.split_once(':'){
+        Some((expression,rest))=>{
+            let column=line[..suffix_offset(line,condition)].chars().count()+1;
+            let condition=Condition::parse(expression.trim_end(),path,n,column)?;
+            Ok((Some(condition),rest.trim_start()))
+        }
+        None=>Err(ParseError::new(path,n,line,line.len(),ParseErrorKind::ExpectedConditionEnd)),
+    }
+}
+
+/// Разбор присваиваний `[имя] = [выражение], ...` - окончания строки `line`.
+fn parse_effects(effects:&str,line:&str,n:usize,path:&Path)->Result<Vec<Assignment>,ParseError>{
+    let mut assignments=Vec::new();
+    let mut offset=suffix_offset(line,effects);
+
+    for assignment in effects.split(','){
+        let column=line[..offset].chars().count()+1;
+        assignments.push(Assignment::parse(assignment,path,n,column)?);
+        offset+=assignment.len()+1;
+    }
+
+    Ok(assignments)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn parse(source:&str)->Result<Dialogue,ParseError>{
+        Dialogue::parse(source,Path::new("test.txt"))
+    }
+
+    /// Строка, символ и вид ошибки разбора.
+    fn error(source:&str)->(usize,usize,ParseErrorKind){
+        let e=parse(source).unwrap_err();
+        (e.line,e.column,e.kind)
+    }
+
+    fn options(dialogue:&Dialogue,index:usize)->&[ChoiceOption]{
+        match dialogue.get(index){
+            Some(Step::Choice(choice))=>&choice.options,
+            step=>panic!("ожидался выбор, а не {:?}",step),
+        }
+    }
+
+    #[test]
+    fn gendered_choice(){
+        let dialogue=parse("{\n}\n* Я {согласен|согласна} -> X\n* {Сам|Сама} пойду | help = 1\n").unwrap();
+        let options=options(&dialogue,0);
+
+        assert_eq!(options[0].text,"Я {согласен|согласна}");
+        assert_eq!(options[0].target.as_deref(),Some("X"));
+        assert!(options[0].effects.is_empty());
+
+        assert_eq!(options[1].text,"{Сам|Сама} пойду");
+        assert_eq!(options[1].target,None);
+        assert_eq!(options[1].effects.len(),1);
+        assert_eq!(options[1].effects[0].name,"help");
+    }
+
+    const example:&str="\
+{
+    А = Алиса.улыбка (Left)
+    Б = Борис
+}
+А - Привет, {}!
+if brave: Б - Ты {смелый|смелая}
+# выбор
+* Идём -> bridge | brave = true, coins = coins - 1
+* if coins > 0: Остаться
+set coins = coins + 1
+_ - Тишина
+{} - Я здесь
+";
+
+    #[test]
+    fn parse_dialogue(){
+        let dialogue=parse(example).unwrap();
+
+        assert_eq!(dialogue.characters(),&[
+            Character{
+                short_name:"А".to_string(),
+                name:"Алиса".to_string(),
+                feature:Some("улыбка".to_string()),
+                position:Position::Left,
+            },
+            Character{
+                short_name:"Б".to_string(),
+                name:"Борис".to_string(),
+                feature:None,
+                position:Position::Center,
+            },
+        ]);
+        assert_eq!(dialogue.len(),6);
+
+        match dialogue.get(1){
+            Some(Step::Line(line))=>{
+                assert_eq!(line.speaker,Speaker::Character(1));
+                assert_eq!(line.text,"Ты {смелый|смелая}");
+                assert_eq!(line.condition.as_ref().map(|condition|condition.column),Some(4));
+            }
+            step=>panic!("ожидалась строка, а не {:?}",step),
+        }
+
+        let options=options(&dialogue,2);
+        assert_eq!(options.len(),2);
+        assert_eq!(options[0].target.as_deref(),Some("bridge"));
+        assert_eq!((options[0].line,options[0].column),(8,11));
+        assert_eq!(options[0].effects.len(),2);
+        assert!(options[1].condition.is_some());
+
+        assert!(matches!(dialogue.get(3),Some(Step::Set{..})));
+        assert!(matches!(dialogue.get(4),Some(Step::Line(Line{speaker:Speaker::Narrator,..}))));
+        assert!(matches!(dialogue.get(5),Some(Step::Line(Line{speaker:Speaker::Player,..}))));
+
+        assert_eq!(dialogue.anchor("выбор"),Some(2));
+        assert_eq!(dialogue.anchor_before(4),Some(("выбор",2)));
+        assert_eq!(dialogue.anchor_before(1),None);
+    }
+
+    #[test]
+    fn step_hash_ignores_position(){
+        let first=parse("{\n}\n_ - Раз\n_ - Два\n").unwrap();
+        let second=parse("{\n}\n_ - Два\n").unwrap();
+
+        assert_eq!(first.step_hash(1),second.step_hash(0));
+        assert_ne!(first.step_hash(0),first.step_hash(1));
+        assert_eq!(first.step_hash(2),None);
+    }
+
+    #[test]
+    fn malformed_header(){
+        assert_eq!(error(""),(1,1,ParseErrorKind::ExpectedHeader));
+        assert_eq!(error("\n  А - текст\n"),(2,3,ParseErrorKind::ExpectedHeader));
+        assert_eq!(error("{\n    А = Алиса\n"),(1,1,ParseErrorKind::UnclosedHeader));
+        assert_eq!(error("{\n    А Алиса\n}\n"),(2,5,ParseErrorKind::ExpectedCharacterDefinition));
+        assert_eq!(error("{\n    А = Алиса.\n}\n"),(2,5,ParseErrorKind::ExpectedCharacterDefinition));
+        assert_eq!(error("{\n    _ = Алиса\n}\n"),(2,5,ParseErrorKind::ReservedShortName("_".to_string())));
+        assert_eq!(error("{\n    А = Алиса (Up)\n}\n"),(2,16,ParseErrorKind::UnknownPosition("Up".to_string())));
+
+        let kind=ParseErrorKind::DuplicateShortName{
+            short_name:"А".to_string(),
+            first_line:2,
+        };
+        assert_eq!(error("{\n    А = Алиса\n    А = Анна\n}\n"),(3,5,kind));
+
+        let mut source="{\n".to_string();
+        for c in 0..=max_characters{
+            source.push_str(&format!("    П{} = Персонаж\n",c));
+        }
+        source.push_str("}\n");
+        assert_eq!(error(&source),(max_characters+2,5,ParseErrorKind::TooManyCharacters(max_characters)));
+    }
+
+    #[test]
+    fn malformed_steps(){
+        assert_eq!(error("{\n}\n  А: текст\n"),(3,3,ParseErrorKind::ExpectedLineSeparator));
+        assert_eq!(error("{\n}\nВ - текст\n"),(3,1,ParseErrorKind::UndefinedShortName("В".to_string())));
+        assert_eq!(error("{\n}\n* -> bridge\n"),(3,1,ParseErrorKind::EmptyChoiceText));
+        assert_eq!(error("{\n}\n* Идём ->  \n"),(3,10,ParseErrorKind::EmptyChoiceTarget));
+        assert_eq!(error("{\n}\nif brave _ - текст\n"),(3,19,ParseErrorKind::ExpectedConditionEnd));
+        assert_eq!(error("{\n}\n_ - Я {пришёл\n"),(3,7,ParseErrorKind::UnclosedSubstitution));
+        assert_eq!(error("{\n}\n#  \n"),(3,1,ParseErrorKind::EmptyAnchor));
+
+        let kind=ParseErrorKind::DuplicateAnchor{
+            name:"м".to_string(),
+            first_line:3,
+        };
+        assert_eq!(error("{\n}\n# м\n_ - текст\n# м\n"),(5,3,kind));
+
+        // Ошибка в выражении - номер символа внутри строки
+        let (line,column,_)=error("{\n}\nset coins = 1 +\n");
+        assert_eq!((line,column),(3,16));
+    }
+
+    #[test]
+    fn unknown_target(){
+        let page_table=PageTable::parse("bridge {\n    wallpaper = w\n    dialogue = d\n}\n",Path::new("page_table.txt")).unwrap();
+        let path=Path::new("test.txt");
+
+        assert_eq!(parse("{\n}\n* Идём -> bridge\n").unwrap().check_targets(&page_table,path),Ok(()));
+
+        let e=parse("{\n}\n* Идём -> river\n").unwrap().check_targets(&page_table,path).unwrap_err();
+        assert_eq!((e.line,e.column,e.kind),(3,11,ParseErrorKind::UnknownPage("river".to_string())));
+    }
+}
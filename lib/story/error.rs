@@ -0,0 +1,212 @@
+use std::{
+    fmt,
+    io,
+    path::{Path,PathBuf},
+};
+
+/// Ошибка загрузки файлов истории.
+#[derive(Debug)]
+pub enum StoryError{
+    /// Файл не удалось прочитать.
+    Io(PathBuf,io::Error),
+    /// Файл прочитан, но содержит ошибку.
+    Parse(ParseError),
+    /// Нет файла ресурса, упомянутого в файле `referenced_by`.
+    MissingFile{
+        path:PathBuf,
+        referenced_by:PathBuf,
+    },
+}
+
+impl fmt::Display for StoryError{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            StoryError::Io(path,error)=>write!(f,"{}: {}",path.display(),error),
+            StoryError::Parse(error)=>error.fmt(f),
+            StoryError::MissingFile{path,referenced_by}=>
+                write!(f,"{}: файл не найден (используется в {})",path.display(),referenced_by.display()),
+        }
+    }
+}
+
+impl std::error::Error for StoryError{}
+
+impl From<ParseError> for StoryError{
+    fn from(error:ParseError)->StoryError{
+        StoryError::Parse(error)
+    }
+}
+
+/// Ошибка разбора с указанием места в файле.
+#[derive(Clone,Debug,PartialEq)]
+pub struct ParseError{
+    pub path:PathBuf,
+    /// Номер строки, начиная с 1.
+    pub line:usize,
+    /// Номер символа в строке, начиная с 1.
+    pub column:usize,
+    pub kind:ParseErrorKind,
+}
+
+impl ParseError{
+    /// `offset` - смещение в байтах от начала строки `source_line`.
+    pub fn new(path:&Path,line:usize,source_line:&str,offset:usize,kind:ParseErrorKind)->ParseError{
+        Self{
+            path:path.to_path_buf(),
+            line,
+            column:source_line[..offset].chars().count()+1,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for ParseError{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        write!(f,"{}:{}:{}: {}",self.path.display(),self.line,self.column,self.kind)
+    }
+}
+
+impl std::error::Error for ParseError{}
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum ParseErrorKind{
+    // Таблица страниц \\
+
+    /// Строка заголовка блока не заканчивается на `{`.
+    ExpectedBlockStart,
+    /// Блок не закрыт до конца файла.
+    UnclosedBlock,
+    /// `}` вне блока.
+    UnexpectedBlockEnd,
+    /// Пустое название страницы.
+    EmptyLabel,
+    /// Название уже использовано в строке `first_line`.
+    DuplicateLabel{
+        label:String,
+        first_line:usize,
+    },
+    /// Ожидалась запись вида `ключ = значение`.
+    ExpectedKeyValue,
+    UnknownKey(String),
+    DuplicateKey(String),
+    MissingKey(&'static str),
+    EmptyValue(String),
+
+    // Диалоги \\
+
+    /// Файл диалога должен начинаться с `{`.
+    ExpectedHeader,
+    /// Заголовок не закрыт `}`.
+    UnclosedHeader,
+    /// Ожидалось объявление `[краткое имя] = [полное имя].[черта] (положение)`.
+    ExpectedCharacterDefinition,
+    /// `_` и `{}` нельзя объявлять.
+    ReservedShortName(String),
+    /// Краткое имя уже объявлено в строке `first_line`.
+    DuplicateShortName{
+        short_name:String,
+        first_line:usize,
+    },
+    UnknownPosition(String),
+    /// Персонажей в заголовке больше, чем можно показать (`max_characters`).
+    TooManyCharacters(usize),
+    /// Ожидалась строка вида `[краткое имя] - [текст]`.
+    ExpectedLineSeparator,
+    /// Краткое имя не объявлено в заголовке.
+    UndefinedShortName(String),
+    /// Пустой текст варианта выбора.
+    EmptyChoiceText,
+    /// Пустое название страницы после `->`.
+    EmptyChoiceTarget,
+    /// Переход на страницу, которой нет в таблице страниц.
+    UnknownPage(String),
+    /// Условие `if` не закрыто `:`.
+    ExpectedConditionEnd,
+    /// `{` не закрыта `}`.
+    UnclosedSubstitution,
+    /// Ожидалось `{}` или `{[мужской род]|[женский род]}`.
+    InvalidSubstitution(String),
+    /// Пустое название метки `#`.
+    EmptyAnchor,
+    /// Метка уже поставлена в строке `first_line`.
+    DuplicateAnchor{
+        name:String,
+        first_line:usize,
+    },
+
+    // Переменные \\
+
+    /// Имя переменной: буквы, цифры и `_`, не начинается с цифры.
+    InvalidVariableName(String),
+    /// Переменная уже объявлена в строке `first_line`.
+    DuplicateVariable{
+        name:String,
+        first_line:usize,
+    },
+    /// Начальное значение - не целое число и не `true/false`.
+    InvalidValue(String),
+    /// Ожидалось присваивание `[имя] = [выражение]`.
+    ExpectedAssignment,
+    InvalidExpression(String),
+    /// Переменная не объявлена в файле переменных.
+    UndefinedVariable(String),
+}
+
+impl fmt::Display for ParseErrorKind{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            ParseErrorKind::ExpectedBlockStart=>write!(f,"ожидалось `{{` в конце строки"),
+            ParseErrorKind::UnclosedBlock=>write!(f,"блок не закрыт `}}`"),
+            ParseErrorKind::UnexpectedBlockEnd=>write!(f,"лишняя `}}` вне блока"),
+            ParseErrorKind::EmptyLabel=>write!(f,"пустое название страницы"),
+            ParseErrorKind::DuplicateLabel{label,first_line}=>
+                write!(f,"страница `{}` уже определена в строке {}",label,first_line),
+            ParseErrorKind::ExpectedKeyValue=>write!(f,"ожидалась запись `ключ = значение`"),
+            ParseErrorKind::UnknownKey(key)=>write!(f,"неизвестный ключ `{}`",key),
+            ParseErrorKind::DuplicateKey(key)=>write!(f,"ключ `{}` указан повторно",key),
+            ParseErrorKind::MissingKey(key)=>write!(f,"не указан ключ `{}`",key),
+            ParseErrorKind::EmptyValue(key)=>write!(f,"пустое значение ключа `{}`",key),
+
+            ParseErrorKind::ExpectedHeader=>write!(f,"ожидался заголовок диалога `{{`"),
+            ParseErrorKind::UnclosedHeader=>write!(f,"заголовок диалога не закрыт `}}`"),
+            ParseErrorKind::ExpectedCharacterDefinition=>
+                write!(f,"ожидалось `[краткое имя] = [полное имя].[черта] (положение)`"),
+            ParseErrorKind::ReservedShortName(short_name)=>
+                write!(f,"краткое имя `{}` зарезервировано",short_name),
+            ParseErrorKind::DuplicateShortName{short_name,first_line}=>
+                write!(f,"краткое имя `{}` уже объявлено в строке {}",short_name,first_line),
+            ParseErrorKind::UnknownPosition(position)=>write!(
+                f,
+                "неизвестное положение `{}` (Left, LeftCenter, CenterLeft, Center, CenterRight, RightCenter, Right)",
+                position
+            ),
+            ParseErrorKind::TooManyCharacters(max)=>
+                write!(f,"в диалоге можно объявить не больше {} персонажей",max),
+            ParseErrorKind::ExpectedLineSeparator=>write!(f,"ожидалось `[краткое имя] - [текст]`"),
+            ParseErrorKind::UndefinedShortName(short_name)=>
+                write!(f,"краткое имя `{}` не объявлено в заголовке",short_name),
+            ParseErrorKind::EmptyChoiceText=>write!(f,"пустой текст варианта выбора"),
+            ParseErrorKind::EmptyChoiceTarget=>write!(f,"ожидалось название страницы после `->`"),
+            ParseErrorKind::UnknownPage(label)=>write!(f,"страница `{}` не найдена в таблице страниц",label),
+            ParseErrorKind::ExpectedConditionEnd=>write!(f,"ожидалось `:` после условия"),
+            ParseErrorKind::UnclosedSubstitution=>write!(f,"подстановка не закрыта `}}`"),
+            ParseErrorKind::InvalidSubstitution(substitution)=>write!(
+                f,
+                "неверная подстановка `{}` (ожидалось `{{}}` или `{{[мужской род]|[женский род]}}`)",
+                substitution
+            ),
+            ParseErrorKind::EmptyAnchor=>write!(f,"ожидалось название метки после `#`"),
+            ParseErrorKind::DuplicateAnchor{name,first_line}=>
+                write!(f,"метка `{}` уже поставлена в строке {}",name,first_line),
+
+            ParseErrorKind::InvalidVariableName(name)=>write!(f,"недопустимое имя переменной `{}`",name),
+            ParseErrorKind::DuplicateVariable{name,first_line}=>
+                write!(f,"переменная `{}` уже объявлена в строке {}",name,first_line),
+            ParseErrorKind::InvalidValue(value)=>
+                write!(f,"недопустимое значение `{}` (ожидалось целое число или true/false)",value),
+            ParseErrorKind::ExpectedAssignment=>write!(f,"ожидалось `[имя] = [выражение]`"),
+            ParseErrorKind::InvalidExpression(message)=>write!(f,"{}",message),
+            ParseErrorKind::UndefinedVariable(name)=>write!(f,"переменная `{}` не объявлена",name),
+        }
+    }
+}
@@ -0,0 +1,565 @@
+use super::Variables;
+
+use std::fmt;
+
+/// Значение переменной или выражения.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Value{
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl Value{
+    pub fn value_type(&self)->Type{
+        match self{
+            Value::Integer(_)=>Type::Integer,
+            Value::Boolean(_)=>Type::Boolean,
+        }
+    }
+
+    /// Разбор значения: целое число или `true`/`false`.
+    pub fn parse(text:&str)->Option<Value>{
+        match text{
+            "true"=>Some(Value::Boolean(true)),
+            "false"=>Some(Value::Boolean(false)),
+            _=>text.parse().ok().map(Value::Integer),
+        }
+    }
+}
+
+impl fmt::Display for Value{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            Value::Integer(value)=>value.fmt(f),
+            Value::Boolean(value)=>value.fmt(f),
+        }
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Type{
+    Integer,
+    Boolean,
+}
+
+impl fmt::Display for Type{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            Type::Integer=>write!(f,"число"),
+            Type::Boolean=>write!(f,"true/false"),
+        }
+    }
+}
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Operator{
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+/// Выражение условий и присваиваний.
+///
+/// Операции в порядке убывания приоритета:
+/// `-` (унарный), `* /`, `+ -`, `== != < <= > >=`, `not`, `and`, `or`.
+/// Значения - целые числа, `true` и `false`, имена переменных.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Expression{
+    Value(Value),
+    /// `column` - номер символа в выражении, начиная с 0
+    Variable{
+        name:String,
+        column:usize,
+    },
+    Negate{
+        operand:Box<Expression>,
+        column:usize,
+    },
+    Not{
+        operand:Box<Expression>,
+        column:usize,
+    },
+    Binary{
+        operator:Operator,
+        left:Box<Expression>,
+        right:Box<Expression>,
+        column:usize,
+    },
+}
+
+/// Ошибка выражения с номером символа в выражении (начиная с 0).
+#[derive(Clone,Debug,PartialEq)]
+pub struct ExpressionError{
+    pub column:usize,
+    pub message:String,
+}
+
+impl Expression{
+    pub fn parse(source:&str)->Result<Expression,ExpressionError>{
+        let tokens=tokenize(source)?;
+        let mut parser=Parser{
+            tokens,
+            position:0,
+            end:source.chars().count(),
+        };
+
+        let expression=parser.or()?;
+
+        match parser.tokens.get(parser.position){
+            Some((column,token))=>Err(ExpressionError{
+                column:*column,
+                message:format!("лишнее `{}`",token),
+            }),
+            None=>Ok(expression),
+        }
+    }
+
+    /// Проверка типов. `variable_type` возвращает тип объявленной переменной.
+    /// Ошибка - номер символа в выражении и её описание.
+    pub fn check<F:Fn(&str)->Option<Type>>(&self,variable_type:&F)->Result<Type,ExpressionError>{
+        let mismatch=|column:usize,expected:Type,found:Type|ExpressionError{
+            column,
+            message:format!("ожидалось значение типа `{}`, а не `{}`",expected,found),
+        };
+
+        match self{
+            Expression::Value(value)=>Ok(value.value_type()),
+
+            Expression::Variable{name,column}=>match variable_type(name){
+                Some(value_type)=>Ok(value_type),
+                None=>Err(ExpressionError{
+                    column:*column,
+                    message:format!("переменная `{}` не объявлена",name),
+                }),
+            }
+
+            Expression::Negate{operand,column}=>match operand.check(variable_type)?{
+                Type::Integer=>Ok(Type::Integer),
+                found=>Err(mismatch(*column,Type::Integer,found)),
+            }
+
+            Expression::Not{operand,column}=>match operand.check(variable_type)?{
+                Type::Boolean=>Ok(Type::Boolean),
+                found=>Err(mismatch(*column,Type::Boolean,found)),
+            }
+
+            Expression::Binary{operator,left,right,column}=>{
+                let left=left.check(variable_type)?;
+                let right=right.check(variable_type)?;
+
+                let (operand,result)=match operator{
+                    Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide=>
+                        (Type::Integer,Type::Integer),
+
+                    Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual=>
+                        (Type::Integer,Type::Boolean),
+
+                    Operator::And | Operator::Or=>(Type::Boolean,Type::Boolean),
+
+                    // Сравнение значений одного типа
+                    Operator::Equal | Operator::NotEqual=>(left,Type::Boolean),
+                };
+
+                if left!=operand{
+                    Err(mismatch(*column,operand,left))
+                }
+                else if right!=operand{
+                    Err(mismatch(*column,operand,right))
+                }
+                else{
+                    Ok(result)
+                }
+            }
+        }
+    }
+
+    /// Вычисление выражения, прошедшего проверку типов.
+    /// Деление на ноль и переполнение дают 0.
+    pub fn evaluate(&self,variables:&Variables)->Value{
+        match self{
+            Expression::Value(value)=>*value,
+
+            // Все объявленные переменные есть в хранилище
+            Expression::Variable{name,..}=>variables.get(name).unwrap_or(Value::Integer(0)),
+
+            Expression::Negate{operand,..}=>match operand.evaluate(variables){
+                Value::Integer(value)=>Value::Integer(value.checked_neg().unwrap_or(0)),
+                value=>value,
+            }
+
+            Expression::Not{operand,..}=>match operand.evaluate(variables){
+                Value::Boolean(value)=>Value::Boolean(!value),
+                value=>value,
+            }
+
+            Expression::Binary{operator,left,right,..}=>{
+                let left=left.evaluate(variables);
+
+                // Сокращённое вычисление
+                match (operator,left){
+                    (Operator::And,Value::Boolean(false))=>return left,
+                    (Operator::Or,Value::Boolean(true))=>return left,
+                    _=>{}
+                }
+
+                let right=right.evaluate(variables);
+
+                match (left,right){
+                    (Value::Integer(left),Value::Integer(right))=>match operator{
+                        Operator::Add=>Value::Integer(left.checked_add(right).unwrap_or(0)),
+                        Operator::Subtract=>Value::Integer(left.checked_sub(right).unwrap_or(0)),
+                        Operator::Multiply=>Value::Integer(left.checked_mul(right).unwrap_or(0)),
+                        Operator::Divide=>Value::Integer(left.checked_div(right).unwrap_or(0)),
+                        Operator::Equal=>Value::Boolean(left==right),
+                        Operator::NotEqual=>Value::Boolean(left!=right),
+                        Operator::Less=>Value::Boolean(left<right),
+                        Operator::LessEqual=>Value::Boolean(left<=right),
+                        Operator::Greater=>Value::Boolean(left>right),
+                        Operator::GreaterEqual=>Value::Boolean(left>=right),
+                        Operator::And | Operator::Or=>Value::Boolean(false),
+                    }
+
+                    (Value::Boolean(left),Value::Boolean(right))=>match operator{
+                        Operator::Equal=>Value::Boolean(left==right),
+                        Operator::NotEqual=>Value::Boolean(left!=right),
+                        Operator::And=>Value::Boolean(left && right),
+                        Operator::Or=>Value::Boolean(left || right),
+                        _=>Value::Boolean(false),
+                    }
+
+                    _=>Value::Boolean(false),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone,Debug,PartialEq)]
+enum Token{
+    Integer(i64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            Token::Integer(value)=>value.fmt(f),
+            Token::Name(name)=>name.fmt(f),
+            Token::Symbol(symbol)=>symbol.fmt(f),
+        }
+    }
+}
+
+const symbols:&[&str]=&["==","!=","<=",">=","<",">","+","-","*","/","(",")"];
+
+/// Разбиение на лексемы с номерами их первых символов.
+fn tokenize(source:&str)->Result<Vec<(usize,Token)>,ExpressionError>{
+    let chars:Vec<char>=source.chars().collect();
+    let mut tokens=Vec::new();
+    let mut c=0;
+
+    while c<chars.len(){
+        let character=chars[c];
+
+        if character.is_whitespace(){
+            c+=1;
+            continue
+        }
+
+        let start=c;
+
+        if character.is_ascii_digit(){
+            while c<chars.len() && chars[c].is_ascii_digit(){
+                c+=1;
+            }
+            let number:String=chars[start..c].iter().collect();
+            match number.parse(){
+                Ok(number)=>tokens.push((start,Token::Integer(number))),
+                Err(_)=>return Err(ExpressionError{
+                    column:start,
+                    message:format!("слишком большое число `{}`",number),
+                }),
+            }
+        }
+        else if character.is_alphabetic() || character=='_'{
+            while c<chars.len() && (chars[c].is_alphanumeric() || chars[c]=='_'){
+                c+=1;
+            }
+            tokens.push((start,Token::Name(chars[start..c].iter().collect())));
+        }
+        else{
+            let rest:String=chars[c..chars.len().min(c+2)].iter().collect();
+            match symbols.iter().find(|symbol|rest.starts_with(**symbol)){
+                Some(&symbol)=>{
+                    tokens.push((start,Token::Symbol(symbol)));
+                    c+=symbol.len();
+                }
+                None=>return Err(ExpressionError{
+                    column:start,
+                    message:format!("неизвестный символ `{}`",character),
+                }),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Разбор методом рекурсивного спуска.
+struct Parser{
+    tokens:Vec<(usize,Token)>,
+    position:usize,
+    // Длина выражения в символах (для ошибок в конце)
+    end:usize,
+}
+
+impl Parser{
+    fn peek(&self)->Option<&Token>{
+        self.tokens.get(self.position).map(|(_,token)|token)
+    }
+
+    fn column(&self)->usize{
+        self.tokens.get(self.position).map_or(self.end,|(column,_)|*column)
+    }
+
+    /// Пропускает лексему, если она совпадает с `expected` (символ или ключевое слово).
+    fn accept(&mut self,expected:&str)->Option<usize>{
+        let found=match self.peek(){
+            Some(Token::Symbol(symbol))=>*symbol==expected,
+            Some(Token::Name(name))=>name==expected,
+            _=>false,
+        };
+
+        if found{
+            let column=self.column();
+            self.position+=1;
+            Some(column)
+        }
+        else{
+            None
+        }
+    }
+
+    fn binary(operator:Operator,left:Expression,right:Expression,column:usize)->Expression{
+        Expression::Binary{
+            operator,
+            left:Box::new(left),
+            right:Box::new(right),
+            column,
+        }
+    }
+
+    fn or(&mut self)->Result<Expression,ExpressionError>{
+        let mut left=self.and()?;
+        while let Some(column)=self.accept("or"){
+            let right=self.and()?;
+            left=Parser::binary(Operator::Or,left,right,column);
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self)->Result<Expression,ExpressionError>{
+        let mut left=self.not()?;
+        while let Some(column)=self.accept("and"){
+            let right=self.not()?;
+            left=Parser::binary(Operator::And,left,right,column);
+        }
+        Ok(left)
+    }
+
+    fn not(&mut self)->Result<Expression,ExpressionError>{
+        match self.accept("not"){
+            Some(column)=>Ok(Expression::Not{
+                operand:Box::new(self.not()?),
+                column,
+            }),
+            None=>self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self)->Result<Expression,ExpressionError>{
+        let left=self.sum()?;
+
+        let operators=[
+            ("==",Operator::Equal),
+            ("!=",Operator::NotEqual),
+            ("<=",Operator::LessEqual),
+            (">=",Operator::GreaterEqual),
+            ("<",Operator::Less),
+            (">",Operator::Greater),
+        ];
+
+        for (symbol,operator) in operators.iter(){
+            if let Some(column)=self.accept(symbol){
+                let right=self.sum()?;
+                return Ok(Parser::binary(*operator,left,right,column))
+            }
+        }
+
+        Ok(left)
+    }
+
+    fn sum(&mut self)->Result<Expression,ExpressionError>{
+        let mut left=self.product()?;
+        loop{
+            if let Some(column)=self.accept("+"){
+                let right=self.product()?;
+                left=Parser::binary(Operator::Add,left,right,column);
+            }
+            else if let Some(column)=self.accept("-"){
+                let right=self.product()?;
+                left=Parser::binary(Operator::Subtract,left,right,column);
+            }
+            else{
+                return Ok(left)
+            }
+        }
+    }
+
+    fn product(&mut self)->Result<Expression,ExpressionError>{
+        let mut left=self.unary()?;
+        loop{
+            if let Some(column)=self.accept("*"){
+                let right=self.unary()?;
+                left=Parser::binary(Operator::Multiply,left,right,column);
+            }
+            else if let Some(column)=self.accept("/"){
+                let right=self.unary()?;
+                left=Parser::binary(Operator::Divide,left,right,column);
+            }
+            else{
+                return Ok(left)
+            }
+        }
+    }
+
+    fn unary(&mut self)->Result<Expression,ExpressionError>{
+        match self.accept("-"){
+            Some(column)=>Ok(Expression::Negate{
+                operand:Box::new(self.unary()?),
+                column,
+            }),
+            None=>self.primary(),
+        }
+    }
+
+    fn primary(&mut self)->Result<Expression,ExpressionError>{
+        let column=self.column();
+
+        if self.accept("(").is_some(){
+            let expression=self.or()?;
+            if self.accept(")").is_none(){
+                return Err(ExpressionError{
+                    column:self.column(),
+                    message:"ожидалась `)`".to_string(),
+                })
+            }
+            return Ok(expression)
+        }
+
+        let expression=match self.peek(){
+            Some(Token::Integer(value))=>Expression::Value(Value::Integer(*value)),
+            Some(Token::Name(name))=>match name.as_str(){
+                "true"=>Expression::Value(Value::Boolean(true)),
+                "false"=>Expression::Value(Value::Boolean(false)),
+                "and" | "or" | "not"=>return Err(ExpressionError{
+                    column,
+                    message:format!("ожидалось значение, а не `{}`",name),
+                }),
+                _=>Expression::Variable{
+                    name:name.clone(),
+                    column,
+                },
+            }
+            Some(token)=>return Err(ExpressionError{
+                column,
+                message:format!("ожидалось значение, а не `{}`",token),
+            }),
+            None=>return Err(ExpressionError{
+                column,
+                message:"ожидалось значение".to_string(),
+            }),
+        };
+
+        self.position+=1;
+        Ok(expression)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn evaluate(source:&str,variables:&Variables)->Value{
+        Expression::parse(source).unwrap().evaluate(variables)
+    }
+
+    fn error_column(source:&str)->usize{
+        Expression::parse(source).unwrap_err().column
+    }
+
+    fn variable_type(name:&str)->Option<Type>{
+        match name{
+            "coins"=>Some(Type::Integer),
+            "brave"=>Some(Type::Boolean),
+            _=>None,
+        }
+    }
+
+    #[test]
+    fn precedence(){
+        let variables=Variables::new();
+
+        assert_eq!(evaluate("1 + 2 * 3",&variables),Value::Integer(7));
+        assert_eq!(evaluate("(1 + 2) * 3",&variables),Value::Integer(9));
+        assert_eq!(evaluate("10 - 4 - 3",&variables),Value::Integer(3));
+        assert_eq!(evaluate("-2 * -3",&variables),Value::Integer(6));
+        assert_eq!(evaluate("1 + 1 == 2 and not false",&variables),Value::Boolean(true));
+        assert_eq!(evaluate("false and true or true",&variables),Value::Boolean(true));
+    }
+
+    #[test]
+    fn variables_and_overflow(){
+        let mut variables=Variables::new();
+        variables.set("coins",Value::Integer(5));
+        variables.set("brave",Value::Boolean(true));
+
+        assert_eq!(evaluate("brave and coins >= 5",&variables),Value::Boolean(true));
+        assert_eq!(evaluate("coins / 0",&variables),Value::Integer(0));
+        assert_eq!(evaluate("9223372036854775807 + coins",&variables),Value::Integer(0));
+    }
+
+    #[test]
+    fn parse_errors(){
+        // Номера символов в выражении, начиная с 0
+        assert_eq!(error_column("1 +"),3);
+        assert_eq!(error_column("(1 + 2"),6);
+        assert_eq!(error_column("1 2"),2);
+        assert_eq!(error_column("coins & 1"),6);
+        assert_eq!(error_column("счёт and"),8);
+        assert_eq!(error_column("99999999999999999999"),0);
+    }
+
+    #[test]
+    fn type_check(){
+        let check=|source:&str|Expression::parse(source).unwrap().check(&variable_type);
+
+        assert_eq!(check("coins * 2 > 3"),Ok(Type::Boolean));
+        assert_eq!(check("-coins"),Ok(Type::Integer));
+        assert_eq!(check("brave == false"),Ok(Type::Boolean));
+
+        assert_eq!(check("coins and brave").unwrap_err().column,6);
+        assert_eq!(check("brave + 1").unwrap_err().column,6);
+        assert_eq!(check("1 + luck").unwrap_err().column,4);
+    }
+}
@@ -0,0 +1,130 @@
+use super::{
+    StoryError,
+    Character,
+};
+
+use crate::resources::{Resources,join};
+
+use std::collections::HashMap;
+
+/// Ключ текстуры персонажа из названия файла `[имя].[черта].png` или `[имя].png`.
+#[derive(Clone,Debug,PartialEq,Eq,Hash)]
+pub struct CharacterKey{
+    pub name:String,
+    pub feature:Option<String>,
+}
+
+impl CharacterKey{
+    /// Разбор названия файла без расширения: имя до последней точки, черта - после
+    /// (как в заголовке диалога).
+    pub fn parse(file_stem:&str)->Option<CharacterKey>{
+        let (name,feature)=match file_stem.rsplit_once('.'){
+            Some((name,feature))=>(name,Some(feature)),
+            None=>(file_stem,None),
+        };
+
+        if name.is_empty() || feature==Some(""){
+            return None
+        }
+
+        Some(Self{
+            name:name.to_string(),
+            feature:feature.map(str::to_string),
+        })
+    }
+
+    pub fn of(character:&Character)->CharacterKey{
+        Self{
+            name:character.name.clone(),
+            feature:character.feature.clone(),
+        }
+    }
+}
+
+/// Картинки истории, найденные в папках персонажей и обоев при запуске
+/// (пути в ресурсах).
+///
+/// По нему находятся текстуры говорящих персонажей и обои страниц,
+/// а файлы с неподходящими названиями не учитываются.
+#[derive(Clone,Debug,Default)]
+pub struct ImageIndex{
+    characters:HashMap<CharacterKey,String>,
+    wallpapers:HashMap<String,String>,
+}
+
+impl ImageIndex{
+    /// Просмотр папок `characters` и `wallpapers` (без вложенных папок).
+    pub fn scan(resources:&Resources,characters:&str,wallpapers:&str)->Result<ImageIndex,StoryError>{
+        let mut index=ImageIndex::default();
+
+        for (stem,path) in scan_png(resources,characters)?{
+            if let Some(key)=CharacterKey::parse(&stem){
+                index.characters.insert(key,path);
+            }
+        }
+
+        index.wallpapers=scan_png(resources,wallpapers)?.into_iter().collect();
+
+        Ok(index)
+    }
+
+    /// Текстура персонажа с учётом дополнительной черты.
+    pub fn character(&self,character:&Character)->Option<&str>{
+        self.characters.get(&CharacterKey::of(character)).map(String::as_str)
+    }
+
+    /// Обои страницы по названию из таблицы страниц.
+    pub fn wallpaper(&self,name:&str)->Option<&str>{
+        self.wallpapers.get(name).map(String::as_str)
+    }
+}
+
+/// Файлы `png` папки: название без расширения и путь.
+fn scan_png(resources:&Resources,directory:&str)->Result<Vec<(String,String)>,StoryError>{
+    let files=match resources.list(directory){
+        Ok(files)=>files,
+        Err(e)=>return Err(StoryError::Io(resources.path(directory),e)),
+    };
+
+    Ok(files.into_iter()
+            .filter_map(|file|{
+                let stem=file.strip_suffix(".png")?.to_string();
+                Some((stem,join(directory,&file)))
+            })
+            .collect())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use crate::story::Dialogue;
+
+    use std::path::Path;
+
+    fn key(name:&str,feature:Option<&str>)->CharacterKey{
+        CharacterKey{
+            name:name.to_string(),
+            feature:feature.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parse_key(){
+        assert_eq!(CharacterKey::parse("Алиса"),Some(key("Алиса",None)));
+        assert_eq!(CharacterKey::parse("Алиса.улыбка"),Some(key("Алиса",Some("улыбка"))));
+        assert_eq!(CharacterKey::parse("Св. Пётр.улыбка"),Some(key("Св. Пётр",Some("улыбка"))));
+
+        assert_eq!(CharacterKey::parse(""),None);
+        assert_eq!(CharacterKey::parse(".улыбка"),None);
+        assert_eq!(CharacterKey::parse("Алиса."),None);
+    }
+
+    #[test]
+    fn key_matches_dialogue_header(){
+        let dialogue=Dialogue::parse("{\n    П = Св. Пётр.улыбка (Left)\n}\n",Path::new("test.txt")).unwrap();
+        let character=&dialogue.characters()[0];
+
+        assert_eq!(CharacterKey::parse(&character.texture_name()),Some(CharacterKey::of(character)));
+    }
+}
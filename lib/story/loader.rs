@@ -0,0 +1,287 @@
+use super::{
+    StoryError,
+    PageTable,
+    Declarations,
+    Dialogue,
+    ImageIndex,
+};
+
+use crate::resources::{Resources,join};
+
+use std::{
+    io::ErrorKind,
+    collections::HashMap,
+};
+
+/// Расположение файлов истории в ресурсах.
+#[derive(Clone,Copy,Debug)]
+pub struct StoryPaths<'a>{
+    /// Таблица страниц
+    pub page_table:&'a str,
+    /// Файл переменных
+    pub variables:&'a str,
+    /// Папка диалогов (`[название].txt`)
+    pub dialogues:&'a str,
+    /// Папка обоев страниц (`[название].png`)
+    pub wallpapers:&'a str,
+    /// Папка текстур персонажей (`[имя].[черта].png`)
+    pub characters:&'a str,
+}
+
+impl<'a> StoryPaths<'a>{
+    pub fn dialogue(&self,name:&str)->String{
+        join(self.dialogues,&format!("{}.txt",name))
+    }
+
+    pub fn wallpaper(&self,name:&str)->String{
+        join(self.wallpapers,&format!("{}.png",name))
+    }
+
+    pub fn character(&self,texture_name:&str)->String{
+        join(self.characters,&format!("{}.png",texture_name))
+    }
+}
+
+/// История, целиком загруженная и проверенная при запуске игры:
+/// таблица страниц, переменные, все диалоги из таблицы и картинки из папок обоев и персонажей.
+///
+/// Также проверяется, что для обоев страниц и персонажей диалогов есть картинки,
+/// так что ошибка в ресурсах обнаруживается до начала игры, а не на её середине.
+#[derive(Clone,Debug)]
+pub struct Story{
+    page_table:PageTable,
+    declarations:Declarations,
+    dialogues:HashMap<String,Dialogue>,
+    images:ImageIndex,
+}
+
+impl Story{
+    /// Загрузка из папки ресурсов или архива.
+    pub fn load(resources:&Resources,paths:&StoryPaths)->Result<Story,StoryError>{
+        let page_table_path=resources.path(paths.page_table);
+
+        let page_table=PageTable::parse(&read_source(resources,paths.page_table)?,&page_table_path)?;
+
+        // Отсутствие файла переменных означает отсутствие переменных
+        let declarations=match resources.read_to_string(paths.variables){
+            Ok(source)=>Declarations::parse(&source,&resources.path(paths.variables))?,
+            Err(e) if e.kind()==ErrorKind::NotFound=>Declarations::default(),
+            Err(e)=>return Err(StoryError::Io(resources.path(paths.variables),e)),
+        };
+        page_table.check_variables(&declarations,&page_table_path)?;
+
+        let images=ImageIndex::scan(resources,paths.characters,paths.wallpapers)?;
+
+        let mut dialogues=HashMap::new();
+
+        for page in page_table.pages(){
+            if images.wallpaper(&page.wallpaper).is_none(){
+                return Err(StoryError::MissingFile{
+                    path:resources.path(&paths.wallpaper(&page.wallpaper)),
+                    referenced_by:page_table_path.clone(),
+                })
+            }
+
+            // Диалог может использоваться на нескольких страницах
+            if dialogues.contains_key(&page.dialogue){
+                continue
+            }
+
+            let dialogue_name=paths.dialogue(&page.dialogue);
+            let dialogue_path=resources.path(&dialogue_name);
+            let dialogue=Dialogue::parse(&read_source(resources,&dialogue_name)?,&dialogue_path)?;
+            dialogue.check_targets(&page_table,&dialogue_path)?;
+            dialogue.check_variables(&declarations,&dialogue_path)?;
+
+            for character in dialogue.characters(){
+                if images.character(character).is_none(){
+                    return Err(StoryError::MissingFile{
+                        path:resources.path(&paths.character(&character.texture_name())),
+                        referenced_by:dialogue_path,
+                    })
+                }
+            }
+
+            dialogues.insert(page.dialogue.clone(),dialogue);
+        }
+
+        Ok(Self{
+            page_table,
+            declarations,
+            dialogues,
+            images,
+        })
+    }
+
+    pub fn page_table(&self)->&PageTable{
+        &self.page_table
+    }
+
+    pub fn declarations(&self)->&Declarations{
+        &self.declarations
+    }
+
+    /// Обои и текстуры персонажей.
+    pub fn images(&self)->&ImageIndex{
+        &self.images
+    }
+
+    /// Диалог `name` из таблицы страниц.
+    pub fn dialogue(&self,name:&str)->Option<&Dialogue>{
+        self.dialogues.get(name)
+    }
+}
+
+fn read_source(resources:&Resources,name:&str)->Result<String,StoryError>{
+    resources.read_to_string(name).map_err(|e|StoryError::Io(resources.path(name),e))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use super::super::{ParseErrorKind,Value};
+
+    use std::{
+        fs::{write,create_dir_all,remove_file,remove_dir_all},
+        path::PathBuf,
+    };
+
+    const paths:StoryPaths<'static>=StoryPaths{
+        page_table:"page_table.txt",
+        variables:"variables.txt",
+        dialogues:"dialogues",
+        wallpapers:"wallpapers",
+        characters:"characters",
+    };
+
+    /// Папка ресурсов во временной папке, удаляемая после теста.
+    struct TempResources{
+        directory:PathBuf,
+    }
+
+    impl TempResources{
+        /// Две страницы с общим диалогом, обои и текстура персонажа.
+        fn new(name:&str)->TempResources{
+            let directory=std::env::temp_dir().join(format!("visual_novel_story_{}_{}",name,std::process::id()));
+            for folder in ["dialogues","wallpapers","characters"]{
+                create_dir_all(directory.join(folder)).unwrap();
+            }
+
+            let resources=Self{
+                directory,
+            };
+            resources.write("page_table.txt","start {\n    wallpaper = forest\n    dialogue = intro\n}\n\nbridge {\n    wallpaper = river\n    dialogue = intro\n}\n");
+            resources.write("dialogues/intro.txt","{\n    А = Алиса.улыбка (Left)\n}\nА - Привет, {}!\n* Идём -> bridge\n");
+            resources.write("wallpapers/forest.png","");
+            resources.write("wallpapers/river.png","");
+            resources.write("characters/Алиса.улыбка.png","");
+            resources
+        }
+
+        fn write(&self,name:&str,contents:&str){
+            write(self.directory.join(name),contents).unwrap();
+        }
+
+        fn remove(&self,name:&str){
+            remove_file(self.directory.join(name)).unwrap();
+        }
+
+        fn load(&self)->Result<Story,StoryError>{
+            Story::load(&Resources::Directory(self.directory.clone()),&paths)
+        }
+    }
+
+    impl Drop for TempResources{
+        fn drop(&mut self){
+            remove_dir_all(&self.directory);
+        }
+    }
+
+    fn missing_file(resources:&TempResources)->(PathBuf,PathBuf){
+        match resources.load(){
+            Err(StoryError::MissingFile{path,referenced_by})=>(path,referenced_by),
+            result=>panic!("ожидалась ошибка отсутствия файла, а не {:?}",result.map(|_|())),
+        }
+    }
+
+    #[test]
+    fn load_story(){
+        let resources=TempResources::new("valid");
+        let story=resources.load().unwrap();
+
+        assert_eq!(story.page_table().len(),2);
+        assert!(story.declarations().is_empty());
+        assert_eq!(story.dialogue("intro").unwrap().characters()[0].name,"Алиса");
+        assert_eq!(story.dialogue("bridge"),None);
+        assert!(story.images().wallpaper("river").is_some());
+    }
+
+    #[test]
+    fn shared_dialogue(){
+        let resources=TempResources::new("shared");
+        let story=resources.load().unwrap();
+
+        let pages=story.page_table().pages();
+        assert_eq!(pages[0].dialogue,pages[1].dialogue);
+
+        let first=story.dialogue(&pages[0].dialogue).unwrap();
+        let second=story.dialogue(&pages[1].dialogue).unwrap();
+        assert!(std::ptr::eq(first,second));
+    }
+
+    #[test]
+    fn variables(){
+        let resources=TempResources::new("variables");
+        resources.write("page_table.txt","start {\n    wallpaper = forest\n    dialogue = intro\n}\n\nbridge {\n    wallpaper = river\n    dialogue = intro\n    condition = brave\n}\n");
+
+        // Без файла переменных условие ссылается на необъявленную переменную
+        match resources.load(){
+            Err(StoryError::Parse(e))=>{
+                assert!(e.path.ends_with("page_table.txt"));
+                assert_eq!(e.line,9);
+                assert!(matches!(e.kind,ParseErrorKind::InvalidExpression(_)));
+            }
+            result=>panic!("ожидалась ошибка разбора, а не {:?}",result.map(|_|())),
+        }
+
+        resources.write("variables.txt","brave = false\n");
+        let story=resources.load().unwrap();
+        assert_eq!(story.declarations().get("brave"),Some(Value::Boolean(false)));
+    }
+
+    #[test]
+    fn missing_wallpaper(){
+        let resources=TempResources::new("wallpaper");
+        resources.remove("wallpapers/river.png");
+
+        let (path,referenced_by)=missing_file(&resources);
+        assert_eq!(path,resources.directory.join("wallpapers/river.png"));
+        assert_eq!(referenced_by,resources.directory.join("page_table.txt"));
+    }
+
+    #[test]
+    fn missing_character(){
+        let resources=TempResources::new("character");
+        resources.remove("characters/Алиса.улыбка.png");
+
+        let (path,referenced_by)=missing_file(&resources);
+        assert_eq!(path,resources.directory.join("characters/Алиса.улыбка.png"));
+        assert_eq!(referenced_by,resources.directory.join("dialogues/intro.txt"));
+    }
+
+    #[test]
+    fn unknown_target(){
+        let resources=TempResources::new("target");
+        resources.write("dialogues/intro.txt","{\n}\n* Идём -> river\n");
+
+        match resources.load(){
+            Err(StoryError::Parse(e))=>{
+                assert!(e.path.ends_with("dialogues/intro.txt"));
+                assert_eq!((e.line,e.column),(3,11));
+                assert_eq!(e.kind,ParseErrorKind::UnknownPage("river".to_string()));
+            }
+            result=>panic!("ожидалась ошибка разбора, а не {:?}",result.map(|_|())),
+        }
+    }
+}
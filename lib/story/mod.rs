@@ -0,0 +1,72 @@
+mod error;
+pub use error::{
+    StoryError,
+    ParseError,
+    ParseErrorKind,
+};
+
+mod dialogue;
+pub use dialogue::{
+    Position,
+    Character,
+    Speaker,
+    Line,
+    ChoiceOption,
+    Choice,
+    Step,
+    Dialogue,
+    max_characters,
+};
+
+mod expression;
+pub use expression::{
+    Value,
+    Type,
+    Operator,
+    Expression,
+    ExpressionError,
+};
+
+mod variables;
+pub use variables::{
+    Variables,
+    Declarations,
+    Condition,
+    Assignment,
+};
+
+mod text;
+pub use text::{
+    Gender,
+    TextError,
+    TextTemplate,
+};
+
+mod bookmark;
+pub use bookmark::{
+    Bookmark,
+    Resolved,
+};
+
+mod page_table;
+pub use page_table::{
+    Page,
+    PageTable,
+};
+
+mod images;
+pub use images::{
+    CharacterKey,
+    ImageIndex,
+};
+
+mod loader;
+pub use loader::{
+    Story,
+    StoryPaths,
+};
+
+/// Смещение первого непробельного символа в байтах.
+pub(crate) fn indent(line:&str)->usize{
+    line.len()-line.trim_start().len()
+}
@@ -0,0 +1,323 @@
+use super::{
+    StoryError,
+    ParseError,
+    ParseErrorKind,
+    Declarations,
+    Condition,
+    indent,
+};
+
+use std::{
+    fmt,
+    fs::{read_to_string,write},
+    io,
+    collections::HashMap,
+    path::Path,
+};
+
+/// Страница игры - блок таблицы страниц.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Page{
+    pub label:String,
+    /// Название обоев (`./resources/images/wallpapers/game/[название].png`)
+    pub wallpaper:String,
+    /// Название диалога (`./resources/dialogues/[название].txt`)
+    pub dialogue:String,
+    /// Страница пропускается, если условие ложно
+    pub condition:Option<Condition>,
+}
+
+/// Таблица страниц (`./resources/page_table.txt`).
+///
+/// Формат блоков:
+/// ```text
+/// [название страницы] - {
+///     wallpaper = [название обоев]
+///     dialogue = [название диалога]
+///     condition = [выражение]
+/// }
+/// ```
+/// Дефис после названия и условие необязательны.
+#[derive(Clone,Debug,PartialEq)]
+pub struct PageTable{
+    pages:Vec<Page>,
+    labels:HashMap<String,usize>,
+}
+
+impl PageTable{
+    pub fn load<P:AsRef<Path>>(path:P)->Result<PageTable,StoryError>{
+        let path=path.as_ref();
+        let source=match read_to_string(path){
+            Ok(source)=>source,
+            Err(e)=>return Err(StoryError::Io(path.to_path_buf(),e)),
+        };
+
+        Ok(PageTable::parse(&source,path)?)
+    }
+
+    /// `path` используется только для сообщений об ошибках.
+    pub fn parse(source:&str,path:&Path)->Result<PageTable,ParseError>{
+        let mut pages=Vec::new();
+        // Название страницы и строка, в которой она определена
+        let mut labels:HashMap<String,(usize,usize)>=HashMap::new();
+
+        let mut lines=source.lines().enumerate().map(|(n,line)|(n+1,line));
+
+        while let Some((n,line))=lines.next(){
+            let trimmed=line.trim();
+            if trimmed.is_empty(){
+                continue
+            }
+
+            let offset=indent(line);
+
+            if trimmed=="}"{
+                return Err(ParseError::new(path,n,line,offset,ParseErrorKind::UnexpectedBlockEnd))
+            }
+
+            // Заголовок блока
+            let label=match trimmed.strip_suffix('{'){
+                Some(label)=>label.trim_end(),
+                None=>return Err(ParseError::new(path,n,line,line.trim_end().len(),ParseErrorKind::ExpectedBlockStart)),
+            };
+            let label=label.strip_suffix('-').unwrap_or(label).trim_end();
+
+            if label.is_empty(){
+                return Err(ParseError::new(path,n,line,offset,ParseErrorKind::EmptyLabel))
+            }
+
+            if let Some(&(first_line,_))=labels.get(label){
+                let kind=ParseErrorKind::DuplicateLabel{
+                    label:label.to_string(),
+                    first_line,
+                };
+                return Err(ParseError::new(path,n,line,offset,kind))
+            }
+
+            let header=(n,line);
+
+            // Тело блока
+            let mut wallpaper:Option<String>=None;
+            let mut dialogue:Option<String>=None;
+            let mut condition:Option<Condition>=None;
+
+            loop{
+                let (n,line)=match lines.next(){
+                    Some(line)=>line,
+                    None=>return Err(ParseError::new(path,header.0,header.1,indent(header.1),ParseErrorKind::UnclosedBlock)),
+                };
+
+                let trimmed=line.trim();
+                if trimmed.is_empty(){
+                    continue
+                }
+                if trimmed=="}"{
+                    break
+                }
+
+                let offset=indent(line);
+
+                let (key,value)=match trimmed.split_once('='){
+                    Some((key,value))=>(key.trim(),value.trim()),
+                    None=>return Err(ParseError::new(path,n,line,offset,ParseErrorKind::ExpectedKeyValue)),
+                };
+
+                if key=="condition"{
+                    if condition.is_some(){
+                        return Err(ParseError::new(path,n,line,offset,ParseErrorKind::DuplicateKey(key.to_string())))
+                    }
+                    if value.is_empty(){
+                        return Err(ParseError::new(path,n,line,offset,ParseErrorKind::EmptyValue(key.to_string())))
+                    }
+
+                    let column=line[..line.trim_end().len()-value.len()].chars().count()+1;
+                    condition=Some(Condition::parse(value,path,n,column)?);
+                    continue
+                }
+
+                let field=match key{
+                    "wallpaper"=>&mut wallpaper,
+                    "dialogue"=>&mut dialogue,
+                    _=>return Err(ParseError::new(path,n,line,offset,ParseErrorKind::UnknownKey(key.to_string()))),
+                };
+
+                if field.is_some(){
+                    return Err(ParseError::new(path,n,line,offset,ParseErrorKind::DuplicateKey(key.to_string())))
+                }
+
+                if value.is_empty(){
+                    return Err(ParseError::new(path,n,line,offset,ParseErrorKind::EmptyValue(key.to_string())))
+                }
+
+                *field=Some(value.to_string());
+            }
+
+            let missing=|key|ParseError::new(path,header.0,header.1,indent(header.1),ParseErrorKind::MissingKey(key));
+
+            let page=Page{
+                label:label.to_string(),
+                wallpaper:wallpaper.ok_or_else(||missing("wallpaper"))?,
+                dialogue:dialogue.ok_or_else(||missing("dialogue"))?,
+                condition,
+            };
+
+            labels.insert(page.label.clone(),(header.0,pages.len()));
+            pages.push(page);
+        }
+
+        Ok(Self{
+            pages,
+            labels:labels.into_iter().map(|(label,(_,index))|(label,index)).collect(),
+        })
+    }
+
+    /// Проверка имён и типов переменных в условиях страниц.
+    pub fn check_variables(&self,declarations:&Declarations,path:&Path)->Result<(),ParseError>{
+        for page in &self.pages{
+            if let Some(condition)=&page.condition{
+                condition.check(declarations,path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Сохранение в формате, пригодном для повторного чтения.
+    pub fn save<P:AsRef<Path>>(&self,path:P)->io::Result<()>{
+        write(path,self.to_string())
+    }
+
+    pub fn len(&self)->usize{
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.pages.is_empty()
+    }
+
+    /// Страницы в порядке следования.
+    pub fn pages(&self)->&[Page]{
+        &self.pages
+    }
+
+    pub fn get(&self,index:usize)->Option<&Page>{
+        self.pages.get(index)
+    }
+
+    /// Порядковый номер страницы по её названию.
+    pub fn index_of(&self,label:&str)->Option<usize>{
+        self.labels.get(label).copied()
+    }
+
+    pub fn page(&self,label:&str)->Option<&Page>{
+        self.index_of(label).map(|index|&self.pages[index])
+    }
+}
+
+impl fmt::Display for PageTable{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        for (c,page) in self.pages.iter().enumerate(){
+            if c!=0{
+                writeln!(f)?;
+            }
+            writeln!(f,"{} - {{",page.label)?;
+            writeln!(f,"    wallpaper = {}",page.wallpaper)?;
+            writeln!(f,"    dialogue = {}",page.dialogue)?;
+            if let Some(condition)=&page.condition{
+                writeln!(f,"    condition = {}",condition.source)?;
+            }
+            writeln!(f,"}}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn parse(source:&str)->Result<PageTable,ParseError>{
+        PageTable::parse(source,Path::new("page_table.txt"))
+    }
+
+    /// Строка, символ и вид ошибки разбора.
+    fn error(source:&str)->(usize,usize,ParseErrorKind){
+        let e=parse(source).unwrap_err();
+        (e.line,e.column,e.kind)
+    }
+
+    const table:&str="\
+start - {
+    wallpaper = forest
+    dialogue = intro
+}
+
+bridge {
+    wallpaper = river
+    dialogue = bridge
+    condition = brave and coins > 2
+}
+";
+
+    #[test]
+    fn parse_pages(){
+        let page_table=parse(table).unwrap();
+
+        assert_eq!(page_table.len(),2);
+        assert_eq!(page_table.index_of("bridge"),Some(1));
+        assert_eq!(page_table.index_of("river"),None);
+
+        let start=page_table.page("start").unwrap();
+        assert_eq!(start.wallpaper,"forest");
+        assert_eq!(start.dialogue,"intro");
+        assert_eq!(start.condition,None);
+
+        let condition=page_table.pages()[1].condition.as_ref().unwrap();
+        assert_eq!(condition.source,"brave and coins > 2");
+        assert_eq!((condition.line,condition.column),(9,17));
+    }
+
+    #[test]
+    fn display_round_trip(){
+        let page_table=parse(table).unwrap();
+        assert_eq!(parse(&page_table.to_string()).unwrap(),page_table);
+    }
+
+    #[test]
+    fn malformed_blocks(){
+        assert_eq!(error("start\n"),(1,6,ParseErrorKind::ExpectedBlockStart));
+        assert_eq!(error("  }\n"),(1,3,ParseErrorKind::UnexpectedBlockEnd));
+        assert_eq!(error(" - {\n}\n"),(1,2,ParseErrorKind::EmptyLabel));
+        assert_eq!(error("start {\n    wallpaper = forest\n"),(1,1,ParseErrorKind::UnclosedBlock));
+    }
+
+    #[test]
+    fn malformed_keys(){
+        assert_eq!(error("start {\n    wallpaper forest\n}\n"),(2,5,ParseErrorKind::ExpectedKeyValue));
+        assert_eq!(error("start {\n    music = theme\n}\n"),(2,5,ParseErrorKind::UnknownKey("music".to_string())));
+        assert_eq!(
+            error("start {\n    dialogue = a\n    dialogue = b\n}\n"),
+            (3,5,ParseErrorKind::DuplicateKey("dialogue".to_string()))
+        );
+        assert_eq!(error("start {\n    dialogue =\n}\n"),(2,5,ParseErrorKind::EmptyValue("dialogue".to_string())));
+        assert_eq!(error("start {\n    dialogue = intro\n}\n"),(1,1,ParseErrorKind::MissingKey("wallpaper")));
+    }
+
+    #[test]
+    fn duplicate_label(){
+        let source="a {\n    wallpaper = w\n    dialogue = d\n}\na {\n    wallpaper = w\n    dialogue = d\n}\n";
+        let kind=ParseErrorKind::DuplicateLabel{
+            label:"a".to_string(),
+            first_line:1,
+        };
+        assert_eq!(error(source),(5,1,kind));
+    }
+
+    #[test]
+    fn condition_error_position(){
+        let e=parse("start {\n    wallpaper = w\n    dialogue = d\n    condition = coins >\n}\n").unwrap_err();
+        assert_eq!(e.line,4);
+        // Конец выражения
+        assert_eq!(e.column,24);
+        assert!(matches!(e.kind,ParseErrorKind::InvalidExpression(_)));
+    }
+}
@@ -0,0 +1,201 @@
+use super::ParseErrorKind;
+
+/// Грамматический род игрока.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Gender{
+    Male,
+    Female,
+}
+
+impl Gender{
+    /// Номер для сохранения.
+    pub fn to_u8(self)->u8{
+        match self{
+            Gender::Male=>0,
+            Gender::Female=>1,
+        }
+    }
+
+    pub fn from_u8(value:u8)->Option<Gender>{
+        match value{
+            0=>Some(Gender::Male),
+            1=>Some(Gender::Female),
+            _=>None,
+        }
+    }
+}
+
+/// Ошибка в подстановке текста.
+#[derive(Clone,Debug,PartialEq)]
+pub struct TextError{
+    /// Смещение `{` в байтах
+    pub offset:usize,
+    pub kind:ParseErrorKind,
+}
+
+/// Текст с подстановками:
+/// `{}` - имя игрока,
+/// `{[мужской род]|[женский род]}` - форма по роду игрока.
+#[derive(Clone,Copy,Debug)]
+pub struct TextTemplate<'a>{
+    text:&'a str,
+}
+
+impl<'a> TextTemplate<'a>{
+    pub fn new(text:&'a str)->TextTemplate<'a>{
+        Self{
+            text,
+        }
+    }
+
+    /// Проверка, что все подстановки закрыты и имеют одну из двух форм.
+    pub fn check(&self)->Result<(),TextError>{
+        self.parts().try_for_each(|part|part.map(|_|()))
+    }
+
+    /// Текст с подставленными именем и формами рода.
+    /// Неверные подстановки выводятся как есть.
+    pub fn format(&self,user_name:&str,gender:Gender)->String{
+        let mut text=String::with_capacity(self.text.len());
+
+        for part in self.parts(){
+            match part{
+                Ok(Part::Text(part))=>text.push_str(part),
+                Ok(Part::Name)=>text.push_str(user_name),
+                Ok(Part::Gendered{male,female})=>match gender{
+                    Gender::Male=>text.push_str(male),
+                    Gender::Female=>text.push_str(female),
+                }
+                Err(e)=>text.push_str(&self.text[e.offset..]),
+            }
+        }
+
+        text
+    }
+
+    fn parts(&self)->Parts<'a>{
+        Parts{
+            text:self.text,
+            offset:0,
+        }
+    }
+}
+
+enum Part<'a>{
+    Text(&'a str),
+    Name,
+    Gendered{
+        male:&'a str,
+        female:&'a str,
+    },
+}
+
+/// Части текста до первой ошибки включительно.
+struct Parts<'a>{
+    text:&'a str,
+    offset:usize,
+}
+
+impl<'a> Iterator for Parts<'a>{
+    type Item=Result<Part<'a>,TextError>;
+
+    fn next(&mut self)->Option<Self::Item>{
+        let rest=&self.text[self.offset..];
+        if rest.is_empty(){
+            return None
+        }
+
+        let start=self.offset;
+
+        match rest.find('{'){
+            // Текст до подстановки
+            Some(0)=>{}
+            Some(end)=>{
+                self.offset+=end;
+                return Some(Ok(Part::Text(&rest[..end])))
+            }
+            None=>{
+                self.offset=self.text.len();
+                return Some(Ok(Part::Text(rest)))
+            }
+        }
+
+        // После ошибки разбор прекращается
+        self.offset=self.text.len();
+
+        let end=match rest.find('}'){
+            Some(end)=>end,
+            None=>return Some(Err(TextError{
+                offset:start,
+                kind:ParseErrorKind::UnclosedSubstitution,
+            })),
+        };
+
+        let substitution=&rest[1..end];
+
+        let part=if substitution.is_empty(){
+            Part::Name
+        }
+        else{
+            match substitution.split_once('|'){
+                Some((male,female)) if !female.contains('|') && !substitution.contains('{')=>Part::Gendered{male,female},
+                _=>return Some(Err(TextError{
+                    offset:start,
+                    kind:ParseErrorKind::InvalidSubstitution(rest[..=end].to_string()),
+                })),
+            }
+        };
+
+        self.offset=start+end+1;
+
+        Some(Ok(part))
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn format(text:&str,gender:Gender)->String{
+        TextTemplate::new(text).format("Ника",gender)
+    }
+
+    #[test]
+    fn substitutions(){
+        assert_eq!(format("Привет, {}!",Gender::Male),"Привет, Ника!");
+        assert_eq!(format("Я {пришёл|пришла}",Gender::Male),"Я пришёл");
+        assert_eq!(format("Я {пришёл|пришла}",Gender::Female),"Я пришла");
+        assert_eq!(format("{} {готов|готова}, {}",Gender::Female),"Ника готова, Ника");
+        assert_eq!(format("{|а}",Gender::Female),"а");
+        assert_eq!(format("без подстановок",Gender::Male),"без подстановок");
+    }
+
+    #[test]
+    fn invalid_substitutions(){
+        let error=|text:&str|TextTemplate::new(text).check().unwrap_err();
+
+        assert_eq!(error("Я {пришёл"),TextError{
+            offset:"Я ".len(),
+            kind:ParseErrorKind::UnclosedSubstitution,
+        });
+        assert_eq!(error("{имя}"),TextError{
+            offset:0,
+            kind:ParseErrorKind::InvalidSubstitution("{имя}".to_string()),
+        });
+        assert_eq!(error("ок {а|б} {а|б|в}"),TextError{
+            offset:"ок {а|б} ".len(),
+            kind:ParseErrorKind::InvalidSubstitution("{а|б|в}".to_string()),
+        });
+
+        // Неверные подстановки выводятся как есть
+        assert_eq!(format("{} и {имя}",Gender::Male),"Ника и {имя}");
+    }
+
+    #[test]
+    fn gender_number(){
+        for gender in [Gender::Male,Gender::Female]{
+            assert_eq!(Gender::from_u8(gender.to_u8()),Some(gender));
+        }
+        assert_eq!(Gender::from_u8(2),None);
+    }
+}
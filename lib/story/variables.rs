@@ -0,0 +1,409 @@
+use super::{
+    StoryError,
+    ParseError,
+    ParseErrorKind,
+    Expression,
+    ExpressionError,
+    Value,
+    Type,
+    indent,
+};
+
+use std::{
+    fs::read_to_string,
+    io::ErrorKind,
+    collections::BTreeMap,
+    path::Path,
+};
+
+/// Хранилище значений переменных истории.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Variables{
+    values:BTreeMap<String,Value>,
+}
+
+impl Variables{
+    pub const fn new()->Variables{
+        Self{
+            values:BTreeMap::new(),
+        }
+    }
+
+    pub fn get(&self,name:&str)->Option<Value>{
+        self.values.get(name).copied()
+    }
+
+    pub fn set(&mut self,name:&str,value:Value){
+        if let Some(old)=self.values.get_mut(name){
+            *old=value;
+        }
+        else{
+            self.values.insert(name.to_string(),value);
+        }
+    }
+
+    pub fn iter(&self)->impl Iterator<Item=(&String,&Value)>{
+        self.values.iter()
+    }
+
+    pub fn clear(&mut self){
+        self.values.clear()
+    }
+
+    /// Добавление недостающих объявленных переменных с начальными значениями
+    /// и удаление переменных, которые больше не объявлены или сменили тип.
+    pub fn declare(&mut self,declarations:&Declarations){
+        self.values.retain(|name,value|{
+            declarations.get(name).is_some_and(|declared|declared.value_type()==value.value_type())
+        });
+
+        for (name,value) in &declarations.values{
+            if !self.values.contains_key(name){
+                self.values.insert(name.clone(),*value);
+            }
+        }
+    }
+}
+
+/// Объявления переменных с начальными значениями (`./resources/variables.txt`).
+///
+/// ```text
+/// [имя] = [целое число или true/false]
+/// ```
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct Declarations{
+    values:BTreeMap<String,Value>,
+}
+
+impl Declarations{
+    /// Отсутствие файла означает отсутствие переменных.
+    pub fn load<P:AsRef<Path>>(path:P)->Result<Declarations,StoryError>{
+        let path=path.as_ref();
+        let source=match read_to_string(path){
+            Ok(source)=>source,
+            Err(e) if e.kind()==ErrorKind::NotFound=>return Ok(Declarations::default()),
+            Err(e)=>return Err(StoryError::Io(path.to_path_buf(),e)),
+        };
+
+        Ok(Declarations::parse(&source,path)?)
+    }
+
+    pub fn parse(source:&str,path:&Path)->Result<Declarations,ParseError>{
+        let mut values=BTreeMap::new();
+        // Строки объявлений
+        let mut lines:BTreeMap<String,usize>=BTreeMap::new();
+
+        for (n,line) in source.lines().enumerate().map(|(n,line)|(n+1,line)){
+            let trimmed=line.trim();
+            if trimmed.is_empty(){
+                continue
+            }
+
+            let offset=indent(line);
+
+            let (name,value)=match trimmed.split_once('='){
+                Some((name,value))=>(name.trim(),value.trim()),
+                None=>return Err(ParseError::new(path,n,line,offset,ParseErrorKind::ExpectedKeyValue)),
+            };
+
+            if !is_name(name){
+                return Err(ParseError::new(path,n,line,offset,ParseErrorKind::InvalidVariableName(name.to_string())))
+            }
+
+            if let Some(&first_line)=lines.get(name){
+                let kind=ParseErrorKind::DuplicateVariable{
+                    name:name.to_string(),
+                    first_line,
+                };
+                return Err(ParseError::new(path,n,line,offset,kind))
+            }
+
+            let value=match Value::parse(value){
+                Some(value)=>value,
+                None=>{
+                    let offset=line.rfind(value).unwrap_or(offset);
+                    return Err(ParseError::new(path,n,line,offset,ParseErrorKind::InvalidValue(value.to_string())))
+                }
+            };
+
+            lines.insert(name.to_string(),n);
+            values.insert(name.to_string(),value);
+        }
+
+        Ok(Self{
+            values,
+        })
+    }
+
+    /// Начальное значение переменной.
+    pub fn get(&self,name:&str)->Option<Value>{
+        self.values.get(name).copied()
+    }
+
+    pub fn variable_type(&self,name:&str)->Option<Type>{
+        self.get(name).map(|value|value.value_type())
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.values.is_empty()
+    }
+}
+
+/// Условие `if [выражение]:` строки, варианта выбора или страницы.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Condition{
+    /// Текст выражения
+    pub source:String,
+    pub expression:Expression,
+    /// Номер строки в файле
+    pub line:usize,
+    /// Номер символа начала выражения, начиная с 1
+    pub column:usize,
+}
+
+impl Condition{
+    /// `column` - номер символа начала `source` в строке `line`, начиная с 1.
+    pub fn parse(source:&str,path:&Path,line:usize,column:usize)->Result<Condition,ParseError>{
+        match Expression::parse(source){
+            Ok(expression)=>Ok(Self{
+                source:source.to_string(),
+                expression,
+                line,
+                column,
+            }),
+            Err(e)=>Err(expression_error(e,path,line,column)),
+        }
+    }
+
+    /// Проверка имён и типов: условие должно быть `true/false`.
+    pub fn check(&self,declarations:&Declarations,path:&Path)->Result<(),ParseError>{
+        match self.expression.check(&|name|declarations.variable_type(name)){
+            Ok(Type::Boolean)=>Ok(()),
+            Ok(found)=>Err(ParseError{
+                path:path.to_path_buf(),
+                line:self.line,
+                column:self.column,
+                kind:ParseErrorKind::InvalidExpression(
+                    format!("условие должно иметь тип `{}`, а не `{}`",Type::Boolean,found)
+                ),
+            }),
+            Err(e)=>Err(expression_error(e,path,self.line,self.column)),
+        }
+    }
+
+    pub fn is_true(&self,variables:&Variables)->bool{
+        self.expression.evaluate(variables)==Value::Boolean(true)
+    }
+}
+
+/// Присваивание `[имя] = [выражение]`.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Assignment{
+    pub name:String,
+    pub value:Expression,
+    /// Номер строки в файле
+    pub line:usize,
+    /// Номер символа начала имени, начиная с 1
+    pub column:usize,
+    /// Номер символа начала выражения, начиная с 1
+    pub value_column:usize,
+}
+
+impl Assignment{
+    /// `column` - номер символа начала `source` в строке `line`, начиная с 1.
+    pub fn parse(source:&str,path:&Path,line:usize,column:usize)->Result<Assignment,ParseError>{
+        let error=|column,kind|ParseError{
+            path:path.to_path_buf(),
+            line,
+            column,
+            kind,
+        };
+
+        let (name,value)=match source.split_once('='){
+            Some(assignment)=>assignment,
+            None=>return Err(error(column,ParseErrorKind::ExpectedAssignment)),
+        };
+
+        let name_column=column+name.chars().count()-name.trim_start().chars().count();
+        let name=name.trim();
+        if !is_name(name){
+            return Err(error(name_column,ParseErrorKind::InvalidVariableName(name.to_string())))
+        }
+
+        let value_column=column+source.chars().count()-value.trim_start().chars().count();
+        let value=value.trim();
+
+        match Expression::parse(value){
+            Ok(expression)=>Ok(Self{
+                name:name.to_string(),
+                value:expression,
+                line,
+                column:name_column,
+                value_column,
+            }),
+            Err(e)=>Err(expression_error(e,path,line,value_column)),
+        }
+    }
+
+    /// Проверка объявления переменной и совпадения типов.
+    pub fn check(&self,declarations:&Declarations,path:&Path)->Result<(),ParseError>{
+        let variable_type=match declarations.variable_type(&self.name){
+            Some(variable_type)=>variable_type,
+            None=>return Err(ParseError{
+                path:path.to_path_buf(),
+                line:self.line,
+                column:self.column,
+                kind:ParseErrorKind::UndefinedVariable(self.name.clone()),
+            }),
+        };
+
+        match self.value.check(&|name|declarations.variable_type(name)){
+            Ok(value_type) if value_type==variable_type=>Ok(()),
+            Ok(value_type)=>Err(ParseError{
+                path:path.to_path_buf(),
+                line:self.line,
+                column:self.value_column,
+                kind:ParseErrorKind::InvalidExpression(
+                    format!("переменная `{}` имеет тип `{}`, а не `{}`",self.name,variable_type,value_type)
+                ),
+            }),
+            Err(e)=>Err(expression_error(e,path,self.line,self.value_column)),
+        }
+    }
+
+    pub fn apply(&self,variables:&mut Variables){
+        let value=self.value.evaluate(variables);
+        variables.set(&self.name,value);
+    }
+}
+
+/// Имя переменной: буквы, цифры и `_`, не начинается с цифры.
+fn is_name(name:&str)->bool{
+    let mut chars=name.chars();
+    match chars.next(){
+        Some(first) if first.is_alphabetic() || first=='_'=>{
+            chars.all(|c|c.is_alphanumeric() || c=='_')
+                && !["true","false","and","or","not"].contains(&name)
+        }
+        _=>false,
+    }
+}
+
+fn expression_error(error:ExpressionError,path:&Path,line:usize,column:usize)->ParseError{
+    ParseError{
+        path:path.to_path_buf(),
+        line,
+        column:column+error.column,
+        kind:ParseErrorKind::InvalidExpression(error.message),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn path()->&'static Path{
+        Path::new("variables.txt")
+    }
+
+    /// Строка, символ и вид ошибки разбора объявлений.
+    fn error(source:&str)->(usize,usize,ParseErrorKind){
+        let e=Declarations::parse(source,path()).unwrap_err();
+        (e.line,e.column,e.kind)
+    }
+
+    fn declarations()->Declarations{
+        Declarations::parse("coins = 3\n\nbrave = false\n",path()).unwrap()
+    }
+
+    #[test]
+    fn parse_declarations(){
+        let declarations=declarations();
+
+        assert_eq!(declarations.get("coins"),Some(Value::Integer(3)));
+        assert_eq!(declarations.variable_type("brave"),Some(Type::Boolean));
+        assert_eq!(declarations.get("luck"),None);
+    }
+
+    #[test]
+    fn malformed_declarations(){
+        assert_eq!(error("coins 3\n"),(1,1,ParseErrorKind::ExpectedKeyValue));
+        assert_eq!(error("  2coins = 3\n"),(1,3,ParseErrorKind::InvalidVariableName("2coins".to_string())));
+        assert_eq!(error("true = 1\n"),(1,1,ParseErrorKind::InvalidVariableName("true".to_string())));
+        assert_eq!(error("coins = three\n"),(1,9,ParseErrorKind::InvalidValue("three".to_string())));
+
+        let kind=ParseErrorKind::DuplicateVariable{
+            name:"coins".to_string(),
+            first_line:1,
+        };
+        assert_eq!(error("coins = 1\ncoins = 2\n"),(2,1,kind));
+    }
+
+    #[test]
+    fn declare_keeps_matching_values(){
+        let mut variables=Variables::new();
+        variables.set("coins",Value::Integer(10));
+        variables.set("brave",Value::Integer(1));
+        variables.set("removed",Value::Boolean(true));
+
+        variables.declare(&declarations());
+
+        // Значение того же типа сохраняется, сменившее тип - сбрасывается
+        assert_eq!(variables.get("coins"),Some(Value::Integer(10)));
+        assert_eq!(variables.get("brave"),Some(Value::Boolean(false)));
+        assert_eq!(variables.get("removed"),None);
+    }
+
+    #[test]
+    fn assignment(){
+        // `set coins = coins + 2` с началом присваивания в 5-м символе
+        let assignment=Assignment::parse("coins = coins + 2",path(),4,5).unwrap();
+        assert_eq!(assignment.name,"coins");
+        assert_eq!((assignment.line,assignment.column,assignment.value_column),(4,5,13));
+        assert_eq!(assignment.check(&declarations(),path()),Ok(()));
+
+        let mut variables=Variables::new();
+        variables.declare(&declarations());
+        assignment.apply(&mut variables);
+        assert_eq!(variables.get("coins"),Some(Value::Integer(5)));
+    }
+
+    #[test]
+    fn assignment_errors(){
+        let error=|source:&str|{
+            let e=Assignment::parse(source,path(),2,1).unwrap_err();
+            (e.line,e.column,e.kind)
+        };
+
+        assert_eq!(error("coins + 1"),(2,1,ParseErrorKind::ExpectedAssignment));
+        assert_eq!(error(" 1x = 1"),(2,2,ParseErrorKind::InvalidVariableName("1x".to_string())));
+        // Конец выражения
+        let (line,column,_)=error("coins = 1 +");
+        assert_eq!((line,column),(2,12));
+
+        let check=|source:&str|{
+            let e=Assignment::parse(source,path(),2,1).unwrap().check(&declarations(),path()).unwrap_err();
+            (e.column,e.kind)
+        };
+
+        assert_eq!(check("luck = 1"),(1,ParseErrorKind::UndefinedVariable("luck".to_string())));
+        assert_eq!(check("coins = brave").0,9);
+    }
+
+    #[test]
+    fn condition(){
+        let condition=Condition::parse("coins > 2 and not brave",path(),3,6).unwrap();
+        assert_eq!(condition.check(&declarations(),path()),Ok(()));
+
+        let mut variables=Variables::new();
+        variables.declare(&declarations());
+        assert!(condition.is_true(&variables));
+
+        // Условие должно быть `true/false`
+        let e=Condition::parse("coins + 1",path(),3,6).unwrap().check(&declarations(),path()).unwrap_err();
+        assert_eq!((e.line,e.column),(3,6));
+
+        let e=Condition::parse("coins >",path(),3,6).unwrap_err();
+        assert_eq!((e.line,e.column),(3,13));
+    }
+}
@@ -57,6 +57,20 @@ impl Button{
         self.background
     }
 
+    /// Замена текста с выравниванием по центру кнопки.
+    pub fn set_text<S:Into<String>>(&mut self,text:S,graphics:&mut Graphics2D){
+        let index=self.text.index();
+        let text=text.into();
+
+        let font=*graphics.get_text_object_font(index);
+        let scale=*graphics.get_text_object_scale(index);
+        let width=graphics.get_font(font).text_width(&text,scale);
+
+        let [x1,_,x2,_]=self.click_area;
+        graphics.get_text_object_position(index)[0]=x1+(x2-x1-width)/2f32;
+        *graphics.get_text_object_text(index)=text;
+    }
+
     /// Проверяет находится ли точка в области кнопки.
     pub fn in_area(&self,x:f32,y:f32)->bool{
         let [x1,y1,x2,y2]=self.click_area;
@@ -73,12 +87,7 @@ impl Button{
     pub fn released(&mut self,x:f32,y:f32)->bool{
         if self.pressed{
             self.pressed=false;
-            if self.in_area(x,y){
-                true
-            }
-            else{
-                false
-            }
+            self.in_area(x,y)
         }
         else{
             false
@@ -19,7 +19,6 @@ use cat_engine::{
     // structs
     graphics::{Graphics,Graphics2D},
     shapes::Rectangle,
-    text::RawGlyphCache,
 
     glium::Surface,
 };
@@ -75,7 +74,7 @@ impl EditTextView{
     }
 
     /// Добавление символа с выравниванием.
-    pub fn push_char<'a>(&mut self,ch:char,graphics:&'a mut Graphics2D){
+    pub fn push_char(&mut self,ch:char,graphics:&mut Graphics2D){
         let font=*graphics.get_text_object_font(self.text_view.index());
 
         let scale=*graphics.get_text_object_scale(self.text_view.index());
@@ -96,7 +95,7 @@ impl EditTextView{
     }
 
     /// Удаление последнего символа с выравниванием.
-    pub fn pop_char<'a>(&mut self,graphics:&'a mut Graphics2D){
+    pub fn pop_char(&mut self,graphics:&mut Graphics2D){
         if let Some(ch)=graphics.get_text_object_text(self.text_view.index()).pop(){
             let font=*graphics.get_text_object_font(self.text_view.index());
 
@@ -118,6 +117,11 @@ impl EditTextView{
         graphics.draw_simple_object(self.background).unwrap();
         self.text_view.draw(graphics)
     }
+
+    pub fn draw_shift<S:Surface>(&self,shift:[f32;2],graphics:&mut Graphics<S>){
+        graphics.draw_shift_simple_object(self.background,shift).unwrap();
+        self.text_view.draw_shift(shift,graphics)
+    }
 }
 
 pub struct EditTextViewSettings<S:Into<String>>{
@@ -147,6 +151,12 @@ impl<S:Into<String>> EditTextViewSettings<S>{
         }
     }
 
+    /// Наибольшая длина текста в байтах.
+    pub fn capacity(mut self,capacity:usize)->EditTextViewSettings<S>{
+        self.capacity=capacity;
+        self
+    }
+
     pub fn background_colour(mut self,colour:Colour)->EditTextViewSettings<S>{
         self.background_colour=colour;
         self
@@ -42,7 +42,7 @@ impl Menu{
         settings:MenuSettings<S,BS,B>,
         graphics:&mut Graphics2D,
     )->Menu{
-        let buttons_text:Vec<String>=settings.buttons_text.into_iter().map(|t|t.into()).collect();
+        let buttons_text:Vec<String>=settings.buttons_text.map(|t|t.into()).collect();
 
         let x0=settings.general.layout[0];        //
         let y0=settings.general.layout[1];        // Положение и размер
@@ -120,6 +120,15 @@ impl Menu{
         }
     }
 
+    /// Количество кнопок.
+    pub fn len(&self)->usize{
+        self.buttons.len()
+    }
+
+    pub fn is_empty(&self)->bool{
+        self.buttons.is_empty()
+    }
+
     pub fn button_index(&self,index:usize)->usize{
         self.buttons[index].background_index()
     }
@@ -211,6 +220,11 @@ impl<S:Into<String>,BS:Into<String>,B:Iterator<Item=BS>> MenuSettings<S,BS,B>{
         self
     }
 
+    pub fn font(mut self,font:usize)->MenuSettings<S,BS,B>{
+        self.font=font;
+        self
+    }
+
     pub fn header_font_size(mut self,font_size:f32)->MenuSettings<S,BS,B>{
         self.header_font_size=font_size;
         self
@@ -7,7 +7,9 @@ pub use button::{
 mod text_view;
 pub use text_view::{
     TextView,
-    TextViewSettings
+    TextViewSettings,
+    wrap_text,
+    fit_text,
 };
 
 mod edit_text_view;
@@ -22,6 +24,12 @@ pub use menu::{
     MenuSettings
 };
 
+mod typewriter;
+pub use typewriter::{
+    Typewriter,
+    TypewriterSettings
+};
+
 #[derive(Clone)]
 pub struct GeneralSettings{
     /// Область для вставки объекта
@@ -109,4 +109,100 @@ impl<S:Into<String>> TextViewSettings<S>{
         self.align.y=align;
         self
     }
-}
\ No newline at end of file
+}
+/// Разбиение текста на строки не шире `width` по словам.
+/// Слово шире `width` разбивается по символам.
+/// `text_width` - ширина текста на экране.
+pub fn wrap_text<F:Fn(&str)->f32>(text:&str,width:f32,text_width:F)->Vec<String>{
+    let mut lines=Vec::new();
+    let mut line=String::new();
+
+    for word in text.split_whitespace(){
+        let candidate=if line.is_empty(){
+            word.to_string()
+        }
+        else{
+            format!("{} {}",line,word)
+        };
+
+        if text_width(&candidate)<=width{
+            line=candidate;
+            continue
+        }
+
+        if !line.is_empty(){
+            lines.push(std::mem::take(&mut line));
+        }
+
+        for character in word.chars(){
+            line.push(character);
+            // Хотя бы один символ в строке
+            if line.chars().count()>1 && text_width(&line)>width{
+                line.pop();
+                lines.push(std::mem::take(&mut line));
+                line.push(character);
+            }
+        }
+    }
+
+    if !line.is_empty() || lines.is_empty(){
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Текст, обрезанный до ширины `width` с многоточием в конце.
+pub fn fit_text<F:Fn(&str)->f32>(text:&str,width:f32,text_width:F)->String{
+    if text_width(text)<=width{
+        return text.to_string()
+    }
+
+    let mut line=String::new();
+    for character in text.chars(){
+        line.push(character);
+        line.push('…');
+        let fits=text_width(&line)<=width;
+        line.pop();
+        if !fits{
+            line.pop();
+            break
+        }
+    }
+
+    line.truncate(line.trim_end().len());
+    line.push('…');
+    line
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // Все символы шириной 10
+    fn width(text:&str)->f32{
+        text.chars().count() as f32*10f32
+    }
+
+    #[test]
+    fn wrap_words(){
+        assert_eq!(wrap_text("один два три четыре",100f32,width),["один два","три четыре"]);
+        assert_eq!(wrap_text("  один   два ",100f32,width),["один два"]);
+        assert_eq!(wrap_text("",100f32,width),[""]);
+    }
+
+    #[test]
+    fn wrap_long_word(){
+        assert_eq!(wrap_text("а длинноеслово б",50f32,width),["а","длинн","оесло","во б"]);
+        // Символ шире строки всё равно выводится
+        assert_eq!(wrap_text("аб",5f32,width),["а","б"]);
+    }
+
+    #[test]
+    fn fit(){
+        assert_eq!(fit_text("Алиса",50f32,width),"Алиса");
+        assert_eq!(fit_text("Александра",50f32,width),"Алек…");
+        assert_eq!(fit_text("Анна Мария",60f32,width),"Анна…");
+        assert_eq!(fit_text("Анна",5f32,width),"…");
+    }
+}
@@ -0,0 +1,260 @@
+use cat_engine::graphics::Graphics2D;
+
+/// Постепенный вывод текста в текстовый объект (эффект печатной машинки).
+///
+/// Работает с любым текстовым объектом по его индексу,
+/// например, с `TextView::index()`.
+pub struct Typewriter{
+    index:usize,
+    text:Vec<char>,
+    // Количество выведенных символов
+    shown:usize,
+    // Накопленная доля следующего символа
+    progress:f32,
+    // Оставшаяся пауза в кадрах
+    pause:u32,
+    settings:TypewriterSettings,
+}
+
+impl Typewriter{
+    pub fn new(text_object:usize,settings:TypewriterSettings)->Typewriter{
+        Self{
+            index:text_object,
+            text:Vec::new(),
+            shown:0,
+            progress:0f32,
+            pause:0,
+            settings,
+        }
+    }
+
+    /// Начинает вывод нового текста, очищая текстовый объект.
+    pub fn set_text<S:Into<String>>(&mut self,text:S,graphics:&mut Graphics2D){
+        self.start(text.into(),0,graphics.get_text_object_text(self.index));
+    }
+
+    /// Начинает вывод текста, первые `shown` символов которого уже выведены
+    /// (восстановление вывода при откате).
+    pub fn set_text_shown<S:Into<String>>(&mut self,text:S,shown:usize,graphics:&mut Graphics2D){
+        self.start(text.into(),shown,graphics.get_text_object_text(self.index));
+    }
+
+    /// Полный текст, включая ещё не выведенную часть.
+    pub fn text(&self)->String{
+        self.text.iter().collect()
+    }
+
+    /// Количество выведенных символов.
+    pub fn shown(&self)->usize{
+        self.shown
+    }
+
+    pub fn is_complete(&self)->bool{
+        self.shown==self.text.len()
+    }
+
+    /// Мгновенный вывод оставшегося текста.
+    pub fn complete(&mut self,graphics:&mut Graphics2D){
+        self.complete_text(graphics.get_text_object_text(self.index));
+    }
+
+    /// Вывод следующих символов, вызывается при `WindowEvent::Update`.
+    pub fn update(&mut self,graphics:&mut Graphics2D){
+        self.update_text(graphics.get_text_object_text(self.index));
+    }
+
+    // Дальше - то же для строки текстового объекта `object_text`
+
+    fn start(&mut self,text:String,shown:usize,object_text:&mut String){
+        self.text=text.chars().collect();
+        self.shown=shown.min(self.text.len());
+        self.progress=0f32;
+        self.pause=0;
+
+        object_text.clear();
+        object_text.extend(&self.text[..self.shown]);
+
+        if self.settings.signs_per_frame<=0f32{
+            self.complete_text(object_text);
+        }
+    }
+
+    fn complete_text(&mut self,object_text:&mut String){
+        object_text.extend(&self.text[self.shown..]);
+        self.shown=self.text.len();
+        self.pause=0;
+    }
+
+    fn update_text(&mut self,object_text:&mut String){
+        if self.is_complete(){
+            return
+        }
+
+        if self.pause!=0{
+            self.pause-=1;
+            return
+        }
+
+        self.progress+=self.settings.signs_per_frame;
+
+        while self.progress>=1f32 && self.shown<self.text.len(){
+            self.progress-=1f32;
+
+            let character=self.text[self.shown];
+            object_text.push(character);
+            self.shown+=1;
+
+            // Пауза после знаков препинания (после последнего из идущих подряд, кроме конца текста)
+            if let Some(pause)=self.settings.pause_after(character){
+                if self.shown<self.text.len() && self.settings.pause_after(self.text[self.shown]).is_none(){
+                    self.pause=pause;
+                    self.progress=0f32;
+                    break
+                }
+            }
+        }
+    }
+}
+
+/// Настройки вывода текста
+pub struct TypewriterSettings{
+    signs_per_frame:f32,
+    /// Знак и пауза после него в кадрах
+    pauses:Vec<(char,u32)>,
+}
+
+impl TypewriterSettings{
+    /// `signs_per_frame` - знаков на кадр, при нуле текст выводится сразу.
+    pub fn new(signs_per_frame:f32)->TypewriterSettings{
+        Self{
+            signs_per_frame,
+            pauses:vec![
+                ('.',12),
+                ('!',12),
+                ('?',12),
+                (',',6),
+                (';',6),
+                (':',6),
+            ],
+        }
+    }
+
+    /// Пауза в кадрах после каждого из знаков `characters`.
+    /// Пауза 0 убирает её.
+    pub fn pause(mut self,characters:&str,frames:u32)->TypewriterSettings{
+        for character in characters.chars(){
+            self.pauses.retain(|&(c,_)|c!=character);
+            if frames!=0{
+                self.pauses.push((character,frames));
+            }
+        }
+        self
+    }
+
+    fn pause_after(&self,character:char)->Option<u32>{
+        self.pauses.iter().find(|&&(c,_)|c==character).map(|&(_,frames)|frames)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    /// Вывод `text` без пауз с `signs_per_frame` знаков на кадр.
+    fn without_pauses(signs_per_frame:f32,text:&str,object_text:&mut String)->Typewriter{
+        let settings=TypewriterSettings::new(signs_per_frame).pause(".,!?;:",0);
+        let mut typewriter=Typewriter::new(0,settings);
+        typewriter.start(text.to_string(),0,object_text);
+        typewriter
+    }
+
+    #[test]
+    fn fractional_speed(){
+        let mut object_text=String::new();
+        let mut typewriter=without_pauses(0.4f32,"абвг",&mut object_text);
+
+        let mut shown=Vec::new();
+        for _ in 0..10{
+            typewriter.update_text(&mut object_text);
+            shown.push(typewriter.shown());
+        }
+
+        assert_eq!(shown,[0,0,1,1,2,2,2,3,3,4]);
+        assert_eq!(object_text,"абвг");
+        assert!(typewriter.is_complete());
+
+        // Несколько знаков за кадр
+        let mut typewriter=without_pauses(2.5f32,"абвгдеж",&mut object_text);
+        typewriter.update_text(&mut object_text);
+        assert_eq!(object_text,"аб");
+        typewriter.update_text(&mut object_text);
+        assert_eq!(object_text,"абвгд");
+    }
+
+    #[test]
+    fn punctuation_pause(){
+        let mut object_text=String::new();
+        let settings=TypewriterSettings::new(1f32).pause(".",3);
+        let mut typewriter=Typewriter::new(0,settings);
+        typewriter.start("Да... Нет.".to_string(),0,&mut object_text);
+
+        let mut shown=Vec::new();
+        while !typewriter.is_complete(){
+            typewriter.update_text(&mut object_text);
+            shown.push(typewriter.shown());
+        }
+
+        // Пауза только после последней точки из идущих подряд и не в конце текста
+        assert_eq!(shown,[1,2,3,4,5,5,5,5,6,7,8,9,10]);
+        assert_eq!(object_text,"Да... Нет.");
+    }
+
+    #[test]
+    fn complete(){
+        let mut object_text=String::new();
+        let settings=TypewriterSettings::new(1f32);
+        let mut typewriter=Typewriter::new(0,settings);
+        typewriter.start("Привет, мир".to_string(),0,&mut object_text);
+
+        for _ in 0..8{
+            typewriter.update_text(&mut object_text);
+        }
+        // Пауза после запятой
+        assert_eq!(object_text,"Привет,");
+        assert!(!typewriter.is_complete());
+
+        typewriter.complete_text(&mut object_text);
+        assert_eq!(object_text,"Привет, мир");
+        assert_eq!(typewriter.shown(),11);
+        assert!(typewriter.is_complete());
+
+        typewriter.update_text(&mut object_text);
+        assert_eq!(object_text,"Привет, мир");
+    }
+
+    #[test]
+    fn instant(){
+        for signs_per_frame in [0f32,-1f32]{
+            let mut object_text="старый текст".to_string();
+            let typewriter=without_pauses(signs_per_frame,"Сразу весь",&mut object_text);
+
+            assert!(typewriter.is_complete());
+            assert_eq!(object_text,"Сразу весь");
+        }
+    }
+
+    #[test]
+    fn start_shown(){
+        let mut object_text="старый текст".to_string();
+        let mut typewriter=without_pauses(1f32,"абвг",&mut object_text);
+        typewriter.start("абвг".to_string(),2,&mut object_text);
+        assert_eq!(object_text,"аб");
+
+        typewriter.update_text(&mut object_text);
+        assert_eq!(object_text,"абв");
+
+        typewriter.start("абвг".to_string(),10,&mut object_text);
+        assert_eq!(object_text,"абвг");
+        assert!(typewriter.is_complete());
+    }
+}
@@ -0,0 +1,297 @@
+//! Реестр загруженных ресурсов: страницы получают их по названиям,
+//! а не по номерам, зависящим от порядка загрузки.
+
+use cat_engine::image::RgbaImage;
+
+use std::{
+    fmt,
+    time::Duration,
+};
+
+/// Текстурный объект.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct TextureHandle(usize);
+
+impl TextureHandle{
+    /// Номер текстурного объекта в `Graphics2D`.
+    pub fn index(self)->usize{
+        self.0
+    }
+}
+
+/// Шрифт.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct FontHandle(usize);
+
+impl FontHandle{
+    /// Номер шрифта в `Graphics2D`.
+    pub fn index(self)->usize{
+        self.0
+    }
+}
+
+/// Аудио трек.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct TrackHandle(usize);
+
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum AssetKind{
+    Texture,
+    Font,
+    Track,
+    Image,
+}
+
+impl fmt::Display for AssetKind{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        let kind=match self{
+            AssetKind::Texture=>"текстура",
+            AssetKind::Font=>"шрифт",
+            AssetKind::Track=>"трек",
+            AssetKind::Image=>"картинка",
+        };
+        write!(f,"{}",kind)
+    }
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum AssetError{
+    /// Ресурс не зарегистрирован.
+    Missing{
+        kind:AssetKind,
+        name:String,
+    },
+    /// Название уже занято ресурсом того же вида.
+    Duplicate{
+        kind:AssetKind,
+        name:String,
+    },
+}
+
+impl fmt::Display for AssetError{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            AssetError::Missing{kind,name}=>write!(f,"нет ресурса `{}` ({})",name,kind),
+            AssetError::Duplicate{kind,name}=>write!(f,"ресурс `{}` ({}) загружен повторно",name,kind),
+        }
+    }
+}
+
+impl std::error::Error for AssetError{}
+
+pub struct Assets{
+    textures:Vec<(String,TextureHandle)>,
+    fonts:Vec<(String,FontHandle)>,
+    // Треки и длительность озвучки
+    tracks:Vec<(String,Option<Duration>)>,
+    // Картинки, загруженные заранее (обои главного меню и т.п.)
+    images:Vec<(String,RgbaImage)>,
+}
+
+impl Assets{
+    pub const fn new()->Assets{
+        Self{
+            textures:Vec::new(),
+            fonts:Vec::new(),
+            tracks:Vec::new(),
+            images:Vec::new(),
+        }
+    }
+
+    /// Регистрация текстурного объекта с номером `index`.
+    pub fn add_texture(&mut self,name:&str,index:usize)->Result<TextureHandle,AssetError>{
+        if find(&self.textures,name).is_some(){
+            return Err(duplicate(AssetKind::Texture,name))
+        }
+        let texture=TextureHandle(index);
+        self.textures.push((name.to_string(),texture));
+        Ok(texture)
+    }
+
+    /// Регистрация шрифта с номером `index`.
+    pub fn add_font(&mut self,name:&str,index:usize)->Result<FontHandle,AssetError>{
+        if find(&self.fonts,name).is_some(){
+            return Err(duplicate(AssetKind::Font,name))
+        }
+        let font=FontHandle(index);
+        self.fonts.push((name.to_string(),font));
+        Ok(font)
+    }
+
+    /// Регистрация трека, добавленного в `AudioWrapper` под названием `name`.
+    pub fn add_track(&mut self,name:&str)->Result<TrackHandle,AssetError>{
+        self.push_track(name,None)
+    }
+
+    /// Регистрация озвучки строки длительностью `length`.
+    pub fn add_voice(&mut self,name:&str,length:Duration)->Result<TrackHandle,AssetError>{
+        self.push_track(name,Some(length))
+    }
+
+    fn push_track(&mut self,name:&str,length:Option<Duration>)->Result<TrackHandle,AssetError>{
+        if find(&self.tracks,name).is_some(){
+            return Err(duplicate(AssetKind::Track,name))
+        }
+        self.tracks.push((name.to_string(),length));
+        Ok(TrackHandle(self.tracks.len()-1))
+    }
+
+    pub fn add_image(&mut self,name:&str,image:RgbaImage)->Result<(),AssetError>{
+        if find(&self.images,name).is_some(){
+            return Err(duplicate(AssetKind::Image,name))
+        }
+        self.images.push((name.to_string(),image));
+        Ok(())
+    }
+
+    pub fn texture(&self,name:&str)->Result<TextureHandle,AssetError>{
+        find(&self.textures,name).copied().ok_or_else(||missing(AssetKind::Texture,name))
+    }
+
+    pub fn font(&self,name:&str)->Result<FontHandle,AssetError>{
+        find(&self.fonts,name).copied().ok_or_else(||missing(AssetKind::Font,name))
+    }
+
+    pub fn track(&self,name:&str)->Result<TrackHandle,AssetError>{
+        match self.tracks.iter().position(|(track,_)|track==name){
+            Some(index)=>Ok(TrackHandle(index)),
+            None=>Err(missing(AssetKind::Track,name)),
+        }
+    }
+
+    /// Название трека для `AudioWrapper::play_track`.
+    pub fn track_name(&self,track:TrackHandle)->Result<&str,AssetError>{
+        match self.tracks.get(track.0){
+            Some((name,_))=>Ok(name),
+            None=>Err(missing(AssetKind::Track,&track.0.to_string())),
+        }
+    }
+
+    /// Длительность озвучки. `None`, если трек не озвучка.
+    pub fn voice_length(&self,track:TrackHandle)->Option<Duration>{
+        self.tracks.get(track.0).and_then(|&(_,length)|length)
+    }
+
+    pub fn image(&self,name:&str)->Result<&RgbaImage,AssetError>{
+        find(&self.images,name).ok_or_else(||missing(AssetKind::Image,name))
+    }
+
+    /// Проверка, что все ресурсы, используемые страницами, загружены.
+    pub fn require(&self,textures:&[&str],fonts:&[&str],tracks:&[&str],images:&[&str])->Result<(),AssetError>{
+        for name in textures{
+            self.texture(name)?;
+        }
+        for name in fonts{
+            self.font(name)?;
+        }
+        for name in tracks{
+            self.track(name)?;
+        }
+        for name in images{
+            self.image(name)?;
+        }
+        Ok(())
+    }
+}
+
+fn find<'a,T>(assets:&'a [(String,T)],name:&str)->Option<&'a T>{
+    assets.iter().find(|(asset,_)|asset==name).map(|(_,asset)|asset)
+}
+
+fn missing(kind:AssetKind,name:&str)->AssetError{
+    AssetError::Missing{
+        kind,
+        name:name.to_string(),
+    }
+}
+
+fn duplicate(kind:AssetKind,name:&str)->AssetError{
+    AssetError::Duplicate{
+        kind,
+        name:name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn image()->RgbaImage{
+        RgbaImage::from_pixel(1,1,cat_engine::image::Rgba([0,0,0,255]))
+    }
+
+    fn assets()->Assets{
+        let mut assets=Assets::new();
+        assets.add_texture("cursor",0).unwrap();
+        assets.add_font("main",1).unwrap();
+        assets.add_track("button_pressed").unwrap();
+        assets.add_image("leaf",image()).unwrap();
+        assets
+    }
+
+    #[test]
+    fn lookup(){
+        let mut assets=assets();
+
+        assert_eq!(assets.texture("cursor").unwrap().index(),0);
+        assert_eq!(assets.font("main").unwrap().index(),1);
+        assert_eq!(assets.image("leaf"),Ok(&image()));
+
+        let track=assets.track("button_pressed").unwrap();
+        assert_eq!(assets.track_name(track),Ok("button_pressed"));
+        assert_eq!(assets.voice_length(track),None);
+
+        let voice=assets.add_voice("Начало/встреча",Duration::from_millis(2500)).unwrap();
+        assert_eq!(assets.track("Начало/встреча"),Ok(voice));
+        assert_eq!(assets.voice_length(voice),Some(Duration::from_millis(2500)));
+
+        assert_eq!(assets.require(&["cursor"],&["main"],&["button_pressed"],&["leaf"]),Ok(()));
+    }
+
+    #[test]
+    fn duplicate(){
+        let mut assets=assets();
+        let duplicate=|kind,name:&str|AssetError::Duplicate{
+            kind,
+            name:name.to_string(),
+        };
+
+        assert_eq!(assets.add_texture("cursor",2).unwrap_err(),duplicate(AssetKind::Texture,"cursor"));
+        assert_eq!(assets.add_font("main",2).unwrap_err(),duplicate(AssetKind::Font,"main"));
+        assert_eq!(assets.add_track("button_pressed").unwrap_err(),duplicate(AssetKind::Track,"button_pressed"));
+        assert_eq!(assets.add_image("leaf",image()).unwrap_err(),duplicate(AssetKind::Image,"leaf"));
+
+        // Названия разных видов ресурсов не пересекаются
+        assert!(assets.add_font("cursor",2).is_ok());
+        // Озвучка - тоже трек
+        assert_eq!(
+            assets.add_voice("button_pressed",Duration::from_secs(1)).unwrap_err(),
+            duplicate(AssetKind::Track,"button_pressed")
+        );
+    }
+
+    #[test]
+    fn missing(){
+        let assets=assets();
+        let missing=|kind,name:&str|AssetError::Missing{
+            kind,
+            name:name.to_string(),
+        };
+
+        assert_eq!(assets.texture("main"),Err(missing(AssetKind::Texture,"main")));
+        assert_eq!(assets.font("cursor"),Err(missing(AssetKind::Font,"cursor")));
+        assert_eq!(assets.track("screenshot"),Err(missing(AssetKind::Track,"screenshot")));
+        assert_eq!(assets.image("cursor"),Err(missing(AssetKind::Image,"cursor")));
+
+        assert_eq!(
+            assets.require(&["cursor"],&["main"],&["button_pressed","screenshot"],&["leaf"]),
+            Err(missing(AssetKind::Track,"screenshot"))
+        );
+
+        // Трек другого реестра
+        let mut other=Assets::new();
+        other.add_track("main_theme").unwrap();
+        let track=other.add_track("screenshot").unwrap();
+        assert_eq!(assets.track_name(track),Err(missing(AssetKind::Track,"1")));
+    }
+}
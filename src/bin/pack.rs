@@ -0,0 +1,100 @@
+//! Сборка архива ресурсов из папки.
+//!
+//! `cargo run --release --bin pack -- [папка] [архив] [--compress]`
+//!
+//! По умолчанию папка - `./resources`, архив - `./resources.pack`.
+//! С `--compress` файлы сжимаются, если это уменьшает их размер.
+//! Игра читает ресурсы из архива, если он лежит рядом с ней.
+
+#![allow(non_snake_case,non_upper_case_globals,non_camel_case_types,unused_must_use)]
+
+use lib::resources::{PackBuilder,Compression,join};
+
+use std::{
+    io,
+    fs::{read,read_dir,write},
+    path::Path,
+    process::exit,
+};
+
+const default_resources_path:&str="./resources";
+const default_pack_path:&str="./resources.pack";
+
+fn main(){
+    let mut compress=false;
+    let mut paths=Vec::new();
+
+    for argument in std::env::args().skip(1){
+        match argument.as_str(){
+            "--compress"=>compress=true,
+            _=>paths.push(argument),
+        }
+    }
+
+    if paths.len()>2{
+        eprintln!("Использование: pack [папка] [архив] [--compress]");
+        exit(2)
+    }
+
+    let resources=paths.first().map_or(default_resources_path,String::as_str);
+    let pack=paths.get(1).map_or(default_pack_path,String::as_str);
+
+    // Названия файлов в архиве - пути относительно папки через `/`
+    let mut files=Vec::new();
+    if let Err(e)=collect_files(Path::new(resources),"",&mut files){
+        eprintln!("{}: {}",resources,e);
+        exit(1)
+    }
+    files.sort();
+
+    let mut builder=PackBuilder::new();
+    let mut compressed=0usize;
+
+    for name in &files{
+        let data=match read(Path::new(resources).join(name)){
+            Ok(data)=>data,
+            Err(e)=>{
+                eprintln!("{}/{}: {}",resources,name,e);
+                exit(1)
+            }
+        };
+
+        if let Compression::Lzss=builder.add(name,data,compress){
+            compressed+=1;
+        }
+    }
+
+    let bytes=builder.into_bytes();
+    if let Err(e)=write(pack,&bytes){
+        eprintln!("{}: {}",pack,e);
+        exit(1)
+    }
+
+    println!("{}: {} файлов ({} сжато), {} байт",pack,files.len(),compressed,bytes.len());
+}
+
+/// Файлы папки `directory` и вложенных папок с путями от корня `prefix`.
+fn collect_files(directory:&Path,prefix:&str,files:&mut Vec<String>)->io::Result<()>{
+    for entry in read_dir(directory)?{
+        let entry=entry?;
+        let name=match entry.file_name().into_string(){
+            Ok(name)=>name,
+            Err(name)=>return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("название не в UTF-8: {:?}",name)
+            )),
+        };
+
+        let name=join(prefix,&name);
+
+        let path=entry.path();
+        if path.is_dir(){
+            collect_files(&path,&name,files)?;
+        }
+        else{
+            files.push(name);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use crate::{
+    // functions
+    load_character_image,
+};
+
+use lib::{
+    colours::{White,Inactive_character_colour},
+    story::{
+        Character,
+        ImageIndex,
+        Position,
+        Speaker,
+    },
+    resources::Resources,
+};
+
+use cat_engine::{
+    // statics
+    window_height,
+    window_width,
+    // structs
+    Window,
+    graphics::{Graphics,Graphics2D},
+    texture::{ImageBase,Texture},
+
+    glium::Surface,
+};
+
+/// Высота персонажей относительно высоты окна.
+const character_height_scale:f32=0.85f32;
+
+/// Расстояние между персонажами в одном положении относительно ширины окна.
+const shared_position_spacing:f32=1f32/16f32;
+
+/// Персонажи текущего диалога на экране.
+///
+/// Текстурные объекты персонажей должны быть добавлены последними,
+/// так как при смене диалога они удаляются с конца.
+pub struct CharacterStage{
+    // Персонажи на экране и их текстурные объекты в порядке объявления
+    characters:Vec<Character>,
+    objects:Vec<usize>,
+    // Говорящий персонаж (рисуется поверх остальных)
+    active:Option<usize>,
+    speaker:Speaker,
+}
+
+impl CharacterStage{
+    pub fn new()->CharacterStage{
+        Self{
+            characters:Vec::new(),
+            objects:Vec::new(),
+            active:None,
+            speaker:Speaker::Narrator,
+        }
+    }
+
+    /// Загрузка текстур персонажей диалога вместо предыдущих
+    /// (текстуры проверены при загрузке истории).
+    pub fn set_characters(&mut self,characters:&[Character],resources:&Resources,images:&ImageIndex,window:&Window,graphics:&mut Graphics2D){
+        self.clear(graphics);
+
+        let height=unsafe{window_height*character_height_scale};
+        let y=unsafe{window_height-height};
+
+        for (c,character) in characters.iter().enumerate(){
+            let path=images.character(character).unwrap();
+            let image=load_character_image(resources,path,height);
+            let width=image.width() as f32;
+
+            // Персонажи в одном положении раздвигаются относительно него
+            let shared=characters.iter().filter(|other|other.position==character.position).count();
+            let place=characters[..c].iter().filter(|other|other.position==character.position).count();
+            let offset=(place as f32-(shared-1) as f32/2f32)*shared_position_spacing*unsafe{window_width};
+
+            let x=position_x(character.position,width)+offset;
+
+            let texture=Texture::from_image(&image,window.display()).unwrap();
+            let texture=graphics.add_texture(texture);
+            let image_base=ImageBase::new([x,y,width,height],White);
+            let object=graphics.add_textured_object(&image_base,texture).unwrap();
+
+            self.objects.push(object);
+        }
+
+        self.characters=characters.to_vec();
+    }
+
+    /// Выделение говорящего: остальные персонажи затемняются.
+    /// При словах без имени (`_`) все персонажи видны одинаково.
+    pub fn set_speaker(&mut self,speaker:Speaker,graphics:&mut Graphics2D){
+        self.speaker=speaker;
+        self.active=match speaker{
+            Speaker::Character(index)=>Some(index),
+            _=>None,
+        };
+
+        for (c,&object) in self.objects.iter().enumerate(){
+            let colour=match speaker{
+                Speaker::Narrator=>White,
+                _=>if Some(c)==self.active{
+                    White
+                }
+                else{
+                    Inactive_character_colour
+                }
+            };
+            *graphics.get_textured_object_colour(object)=colour;
+        }
+    }
+
+    /// Персонажи на экране.
+    pub fn characters(&self)->&[Character]{
+        &self.characters
+    }
+
+    /// Последний говорящий.
+    pub fn speaker(&self)->Speaker{
+        self.speaker
+    }
+
+    pub fn draw_shift<S:Surface>(&self,shift:[f32;2],graphics:&mut Graphics<S>){
+        for (c,&object) in self.objects.iter().enumerate(){
+            if Some(c)!=self.active{
+                graphics.draw_shift_textured_object(object,shift).unwrap();
+            }
+        }
+
+        if let Some(active)=self.active{
+            if let Some(&object)=self.objects.get(active){
+                graphics.draw_shift_textured_object(object,shift).unwrap();
+            }
+        }
+    }
+
+    /// Удаление текстур и текстурных объектов персонажей.
+    pub fn clear(&mut self,graphics:&mut Graphics2D){
+        for _ in 0..self.objects.len(){
+            graphics.remove_last_textured_object();
+        }
+        for _ in 0..self.objects.len(){
+            graphics.remove_last_texture();
+        }
+        self.characters.clear();
+        self.objects.clear();
+        self.active=None;
+    }
+}
+
+/// Положение левого края персонажа шириной `width`.
+fn position_x(position:Position,width:f32)->f32{
+    let screen_width=unsafe{window_width};
+    match position{
+        Position::Left=>0f32,
+        Position::LeftCenter=>screen_width/4f32-width/2f32,
+        Position::CenterLeft=>screen_width*3f32/8f32-width/2f32,
+        Position::Center=>(screen_width-width)/2f32,
+        Position::CenterRight=>screen_width*5f32/8f32-width/2f32,
+        Position::RightCenter=>screen_width*3f32/4f32-width/2f32,
+        Position::Right=>screen_width-width,
+    }
+}
@@ -1,13 +1,51 @@
-use std::{
-    io::{Read,Write},
-    fs::OpenOptions,
+use crate::save_format::{
+    SaveError,
+    begin,
+    finish,
+    verify,
+    load_with_backup,
+    write_bookmark,
+    read_bookmark,
+    write_read_lines,
+    read_read_lines,
+    write_choices,
+    read_choices,
+    write_variables,
+    read_variables,
 };
 
+use lib::{
+    story::{Variables,Gender,Bookmark},
+    binary::{BinaryReader,BinaryError},
+    atomic_file::write_atomic,
+};
+
+use std::collections::BTreeSet;
+
+/// Путь файла настроек.
+pub const settings_path:&'static str="./settings/game_settings";
+
+/// Начало файла настроек.
+const settings_magic:&[u8;4]=b"VNGS";
+/// Текущая версия формата.
+/// Версия 2 добавила контрольную сумму CRC-32 в конце файла,
+/// версия 3 - время игры,
+/// версия 4 - место в истории вместо номеров страницы и строки,
+/// версия 5 - прочитанные строки и режим пропуска,
+/// версия 6 - скорость авточтения.
+const settings_version:u16=6;
+
 pub struct GameSettings{
     pub continue_game:bool, // Флаг продолжения игры
     pub user_name:String,
-    pub saved_page:usize, // Страница на которой остановился пользователь (page_table)
-    pub saved_dialogue:usize, // Место в диалоге на котором остановился пользователь (dialogue_box)
+    pub gender:Gender, // Род игрока для форм `{[мужской род]|[женский род]}`
+    pub saved_position:Bookmark, // Место в истории, на котором остановился пользователь
+    pub choices:Vec<(String,usize)>, // Пройденный путь: страница, на которой сделан выбор, и номер варианта
+    pub variables:Variables, // Переменные истории
+    pub playtime:u64, // Время игры в секундах
+    pub read_lines:BTreeSet<u64>, // Строки, прочитанные за все прохождения (`Bookmark::line_key`)
+    pub skip_all:bool, // Пропуск всех строк, а не только прочитанных
+    pub auto_speed:f32, // Скорость авточтения, знаков в секунду
     pub pages:usize, // Количество страниц в игре
     pub signs_per_frame:f32, // Знаков на кадр
     pub volume:f32, // Громкость игры, 0 - 128
@@ -17,14 +55,21 @@ pub struct GameSettings{
 }
 
 impl GameSettings{
-    /// 
+    ///
     pub const fn new()->GameSettings{
         Self{
             continue_game:false,
             user_name:String::new(),
+            // Изначально история написана от лица девушки
+            gender:Gender::Female,
             pages:0,
-            saved_page:0,
-            saved_dialogue:0,
+            saved_position:Bookmark::new(),
+            choices:Vec::new(),
+            variables:Variables::new(),
+            playtime:0,
+            read_lines:BTreeSet::new(),
+            skip_all:false,
+            auto_speed:15f32,
             signs_per_frame:0.25f32,
             volume:1f32,
             screenshot:0u32,
@@ -33,89 +78,216 @@ impl GameSettings{
         }
     }
 
-    /// Загрузка настроек
-    pub fn load()->GameSettings{
-        let mut settings=GameSettings::new();
+    /// Загрузка настроек.
+    /// Если файл повреждён или отсутствует, используется резервная копия.
+    /// При отсутствии обоих файлов возвращаются настройки по умолчанию.
+    pub fn load()->Result<GameSettings,SaveError>{
+        let settings=load_with_backup(settings_path,GameSettings::from_bytes)?;
+        Ok(settings.unwrap_or_else(GameSettings::new))
+    }
+
+    /// Сохрание настроек (атомарно, с резервной копией предыдущих).
+    pub fn save(&self)->Result<(),SaveError>{
+        write_atomic(settings_path,&self.to_bytes(),|data|GameSettings::from_bytes(data).is_ok())?;
+
+        Ok(())
+    }
 
-        // Общие настройки пользоавателя
-        let mut settings_file=OpenOptions::new().read(true).open("settings/game_settings").unwrap();
-        let mut buffer=[0u8;8];
+    /// Установка позиций для сохранения
+    pub fn set_saved_position(&mut self,position:Bookmark){
+        self.saved_position=position;
+    }
+
+    /// Запись в текущем формате:
+    /// заголовок, версия (`u16`), поля с явной шириной
+    /// и контрольная сумма (`u32`) всех предыдущих байтов.
+    pub fn to_bytes(&self)->Vec<u8>{
+        let mut writer=begin(settings_magic,settings_version);
 
         // Продолжение игры
-        settings_file.read_exact(&mut buffer[0..1]).unwrap();
-        if buffer[0]!=0{
-            settings.continue_game=true;
-            // Имя пользователя при продолжении игры
-            settings_file.read_exact(&mut buffer[0..1]).unwrap();
-            
-            let mut name=vec![0u8;buffer[0] as usize];
-            settings_file.read_exact(&mut name).unwrap();
-            settings.user_name=String::from_utf8(name).unwrap();
-        }
-        // Текущая страница игры
-        settings_file.read_exact(&mut buffer).unwrap();
-        settings.saved_page=usize::from_be_bytes(buffer);
-        // Текущее положение в диалоге на странице
-        settings_file.read_exact(&mut buffer).unwrap();
-        settings.saved_dialogue=usize::from_be_bytes(buffer);
-        //
-        let mut buffer=[0u8;4];
+        writer.write_bool(self.continue_game);
+        writer.write_str(&self.user_name);
+        writer.write_u8(self.gender.to_u8());
+        // Место в истории
+        write_bookmark(&mut writer,&self.saved_position);
+
+        // Сделанные выборы
+        write_choices(&mut writer,&self.choices);
+        // Переменные истории
+        write_variables(&mut writer,&self.variables);
+
         // Количество символов в секунду
-        settings_file.read_exact(&mut buffer).unwrap();
-        settings.signs_per_frame=f32::from_be_bytes(buffer);
+        writer.write_f32(self.signs_per_frame);
         // Значение громкости
-        settings_file.read_exact(&mut buffer).unwrap();
-        settings.volume=f32::from_be_bytes(buffer);
+        writer.write_f32(self.volume);
         // Количество сделанных скриншотов (номер следующего)
-        settings_file.read_exact(&mut buffer).unwrap();
-        settings.screenshot=u32::from_be_bytes(buffer);
-
+        writer.write_u32(self.screenshot);
         // Выбранный монитор
-        let mut buffer=[0u8;8];
-        settings_file.read_exact(&mut buffer).unwrap();
-        settings.monitor=usize::from_be_bytes(buffer);
+        writer.write_u32(self.monitor as u32);
 
-        settings
-    }
+        // Время игры
+        writer.write_u64(self.playtime);
 
-    /// Установка позиций для сохранения
-    pub fn set_saved_position(&mut self,page:usize,dialogue:usize){
-        self.saved_page=page;
-        self.saved_dialogue=dialogue;
+        // Прочитанные строки и режим пропуска
+        write_read_lines(&mut writer,&self.read_lines);
+        writer.write_bool(self.skip_all);
+
+        // Скорость авточтения
+        writer.write_f32(self.auto_speed);
+
+        finish(writer)
     }
 
-    /// Сохрание настроек
-    pub fn save(&mut self){
-        let mut settings_file=OpenOptions::new().write(true).truncate(true).open("settings/game_settings").unwrap();
-        if self.continue_game{
-            settings_file.write_all(&[1]).unwrap(); // Продолжение игры
-            // Имя пользователя при продолжении игры
-            let buffer=self.user_name.as_bytes();
-            let len=buffer.len() as u8;
-            settings_file.write_all(&[len]).unwrap();
-            settings_file.write_all(buffer).unwrap();
+    /// Чтение текущего формата, предыдущих версий или старого формата без заголовка.
+    pub fn from_bytes(data:&[u8])->Result<GameSettings,SaveError>{
+        if !data.starts_with(settings_magic){
+            return GameSettings::from_legacy_bytes(data)
+        }
+
+        let mut reader=BinaryReader::new(&data[settings_magic.len()..]);
+        let version=reader.read_u16()?;
+
+        let data=match version{
+            // Без контрольной суммы
+            1=>data,
+            2..=6=>verify(data)?,
+            _=>return Err(SaveError::UnsupportedVersion(version)),
+        };
+
+        let mut reader=BinaryReader::new(data);
+        reader.read_raw(settings_magic.len()+2)?;
+        let mut settings=GameSettings::new();
+
+        // Продолжение игры
+        settings.continue_game=reader.read_bool()?;
+        settings.user_name=reader.read_string()?;
+        settings.gender=Gender::from_u8(reader.read_u8()?).ok_or(SaveError::InvalidValue("gender"))?;
+        // Место в истории (раньше - номера страницы и строки)
+        if version>=4{
+            settings.saved_position=read_bookmark(&mut reader)?;
         }
         else{
-            settings_file.write_all(&[0]).unwrap(); // Новая игра
+            settings.saved_position.page=reader.read_u64()? as usize;
+            settings.saved_position.line=reader.read_u64()? as usize;
+        }
+
+        // Сделанные выборы
+        settings.choices=read_choices(&mut reader)?;
+        // Переменные истории
+        settings.variables=read_variables(&mut reader)?;
+
+        // Количество символов в секунду
+        settings.signs_per_frame=reader.read_f32()?;
+        // Значение громкости
+        settings.volume=reader.read_f32()?;
+        // Количество сделанных скриншотов (номер следующего)
+        settings.screenshot=reader.read_u32()?;
+        // Выбранный монитор
+        settings.monitor=reader.read_u32()? as usize;
+
+        // Время игры
+        if version>=3{
+            settings.playtime=reader.read_u64()?;
+        }
+
+        // Прочитанные строки и режим пропуска
+        if version>=5{
+            settings.read_lines=read_read_lines(&mut reader)?;
+            settings.skip_all=reader.read_bool()?;
+        }
+
+        // Скорость авточтения
+        if version>=6{
+            settings.auto_speed=reader.read_f32()?;
+        }
+
+        Ok(settings)
+    }
+
+    /// Чтение старого формата без заголовка и версии,
+    /// в котором `usize` записывались восемью байтами.
+    fn from_legacy_bytes(data:&[u8])->Result<GameSettings,SaveError>{
+        let mut reader=BinaryReader::new(data);
+        let mut settings=GameSettings::new();
+
+        // Продолжение игры
+        if reader.read_bool()?{
+            settings.continue_game=true;
+            // Имя пользователя при продолжении игры (длина - один байт)
+            let len=reader.read_u8()? as usize;
+            let name=reader.read_raw(len)?;
+            settings.user_name=String::from_utf8(name.to_vec()).map_err(|_|BinaryError::InvalidUtf8)?;
         }
         // Текущая страница игры
-        let mut buffer=self.saved_page.to_be_bytes();
-        settings_file.write_all(&buffer).unwrap();
+        settings.saved_position.page=reader.read_u64()? as usize;
         // Текущее положение в диалоге на странице
-        buffer=self.saved_dialogue.to_be_bytes();
-        settings_file.write_all(&buffer).unwrap();
+        settings.saved_position.line=reader.read_u64()? as usize;
         // Количество символов в секунду
-        let mut buffer=self.signs_per_frame.to_be_bytes();
-        settings_file.write_all(&buffer).unwrap();
+        settings.signs_per_frame=reader.read_f32()?;
         // Значение громкости
-        buffer=self.volume.to_be_bytes();
-        settings_file.write_all(&buffer).unwrap();
+        settings.volume=reader.read_f32()?;
         // Количество сделанных скриншотов (номер следующего)
-        buffer=self.screenshot.to_be_bytes();
-        settings_file.write_all(&buffer).unwrap();
-
+        settings.screenshot=reader.read_u32()?;
         // Выбранный монитор
-        let buffer=self.monitor.to_be_bytes();
-        settings_file.write_all(&buffer).unwrap();
+        settings.monitor=reader.read_u64()? as usize;
+
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use lib::story::Value;
+
+    #[test]
+    fn round_trip(){
+        let mut settings=GameSettings::new();
+        settings.continue_game=true;
+        settings.user_name="Ника".to_string();
+        settings.gender=Gender::Male;
+        settings.saved_position.page_label="bridge".to_string();
+        settings.saved_position.line=4;
+        settings.choices.push(("start".to_string(),1));
+        settings.variables.set("coins",Value::Integer(3));
+        settings.playtime=3600;
+        settings.read_lines.insert(42);
+        settings.skip_all=true;
+        settings.auto_speed=25f32;
+        settings.volume=0.5f32;
+        settings.screenshot=7;
+
+        let loaded=GameSettings::from_bytes(&settings.to_bytes()).unwrap();
+
+        assert!(loaded.continue_game);
+        assert_eq!(loaded.user_name,settings.user_name);
+        assert_eq!(loaded.gender,settings.gender);
+        assert_eq!(loaded.saved_position,settings.saved_position);
+        assert_eq!(loaded.choices,settings.choices);
+        assert_eq!(loaded.variables,settings.variables);
+        assert_eq!(loaded.playtime,settings.playtime);
+        assert_eq!(loaded.read_lines,settings.read_lines);
+        assert!(loaded.skip_all);
+        assert_eq!(loaded.auto_speed,settings.auto_speed);
+        assert_eq!(loaded.volume,settings.volume);
+        assert_eq!(loaded.screenshot,settings.screenshot);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn unsupported_version(){
+        let mut data=GameSettings::new().to_bytes();
+        data[settings_magic.len()+1]=settings_version as u8+1;
+
+        assert!(matches!(GameSettings::from_bytes(&data),Err(SaveError::UnsupportedVersion(version)) if version==settings_version+1));
+    }
+
+    #[test]
+    fn truncated(){
+        let data=GameSettings::new().to_bytes();
+
+        for len in [2,settings_magic.len()+1,data.len()-1]{
+            assert!(GameSettings::from_bytes(&data[..len]).is_err(),"длина {}",len);
+        }
+    }
+}
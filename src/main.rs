@@ -1,19 +1,33 @@
 #![allow(non_snake_case,non_upper_case_globals,non_camel_case_types,unused_must_use,unused_imports,dead_code)]
 #![cfg_attr(not(debug_assertions),windows_subsystem="windows")]
 
+mod save_format;
+
 mod game_settings;
-use game_settings::GameSettings;
+use game_settings::{GameSettings,settings_path};
+
+mod saves;
+use saves::{save_slots,autosave_slots};
+
+mod assets;
+use assets::{Assets,AssetError};
+
+mod character_stage;
 
 mod pages;
 use pages::{
     SwipeDirection,
     LoadingScreen,
-    MainMenu
+    MainMenu,
+    GamePage,
 };
 
 use lib::{
     *,
     colours::*,
+    story::{Story,StoryPaths,StoryError,max_characters},
+    manifest::{Manifest,ImageRole,TrackCategory},
+    resources::Resources,
 };
 
 use cat_engine::{
@@ -80,56 +94,93 @@ use cat_engine::{
 };
 
 use std::{
-    fs::{metadata,read_dir},
+    fs::{metadata,read_dir,remove_file,write},
     path::{PathBuf,Path},
+    time::Duration,
 };
 
 pub enum Game{
     MainMenu,
     Pause,
     Next,
+    /// Загружено сохранение
+    Load,
     Exit,
 }
 
-// Индексы главных текстурных объектов
-const mouse_cursor_icon_index:usize=0;
-
-const wallpaper_index:usize=1;
-
-/// Картинка для переходов.
-const swipe_screen_index:usize=2;
 const swipe_updates:u8=23;
 
-// Пути ресурсов
-const cursor_icon:&'static str="./resources/images/mouse_icon.png";
+// Ресурсы читаются из архива, если он есть, иначе из папки
+const resources_path:&'static str="./resources";
+const resources_pack_path:&'static str="./resources.pack";
+
+// Список ресурсов (пути в ресурсах указываются относительно их корня)
+const manifest_path:&'static str="manifest.txt";
 
-const audio_tracks_paths:&[&'static str]=&[
-    "./resources/audio/audio.mp3",
-    "./resources/audio/button_pressed.mp3",
-    "./resources/audio/screenshot.mp3",
+// Ресурсы, используемые игрой по названию
+/// Шрифты в порядке номеров (0 - главный, 1 - диалоговый).
+const font_names:&[&'static str]=&[
+    "main",
+    "dialogue",
 ];
 
-const fonts_paths:&[&'static str]=&[
-    "./resources/fonts/main.font",
-    "./resources/fonts/dialogue.font",
+const track_names:&[&'static str]=&[
+    "main_theme",
+    "button_pressed",
+    "screenshot",
 ];
 
-const main_menu_wallpaper_path:&'static str="./resources/images/wallpapers/main_menu_wallpaper.png";
+const image_names:&[&'static str]=&[
+    "cursor",
+    "window_icon",
+    "loading_screen",
+    "dialogue_box",
+    "main_menu_wallpaper",
+    "leaf",
+];
 
-const decoration_image_paths:&[&'static str]=&[
-    "resources/images/rose.png",
+/// Картинки главного меню, загружаемые заранее.
+const menu_image_names:&[&'static str]=&[
+    "main_menu_wallpaper",
+    "leaf",
 ];
 
-// Названия для аудио треков
-const audio_tracks_names:&[&'static str]=&[
-    "main_theme",
-    "button_pressed",
-    "screenshot"
+/// Главные текстурные объекты: курсор, обои и картинка для переходов.
+const texture_names:&[&'static str]=&[
+    "cursor",
+    "wallpaper",
+    "swipe_screen",
 ];
 
+/// Наибольшее число текстурных объектов одновременно:
+/// главные, окно диалога и персонажи, снимок игры в меню паузы
+/// и миниатюры всех слотов сохранения.
+const textured_object_limit:usize=texture_names.len()+1+max_characters+1+save_slots+autosave_slots;
+
+// Пути истории
+const page_table_path:&'static str="page_table.txt";
+const variables_path:&'static str="variables.txt";
+
+const dialogues_path:&'static str="dialogues";
+
+const game_wallpapers_path:&'static str="images/wallpapers/game";
+
+const characters_path:&'static str="images/characters";
+
+const story_paths:StoryPaths<'static>=StoryPaths{
+    page_table:page_table_path,
+    variables:variables_path,
+    dialogues:dialogues_path,
+    wallpapers:game_wallpapers_path,
+    characters:characters_path,
+};
+
+/// Ошибки ресурсов и истории, найденные при запуске (для проверки без консоли).
+const resource_errors_path:&'static str="./resource_errors.txt";
+
 
 // Алфавит для рендеринга текста (остальные символы будут выведены как неопределённые)
-const alphabet:&'static str="АаБбВвГгДдЕеЁёЖжЗзИиЙйКкЛлМмНнОоПпРрСсТтУуФфХхЦцЧчШшЩщЪъЫыЬьЭэЮюЯя1234567890AaBbCcDdEeFfGgHhIiJjKkLlMmNnOoPpQqRrSsTtUuVvWwXxYyZz:();[]!.,";
+const alphabet:&'static str="АаБбВвГгДдЕеЁёЖжЗзИиЙйКкЛлМмНнОоПпРрСсТтУуФфХхЦцЧчШшЩщЪъЫыЬьЭэЮюЯя1234567890AaBbCcDdEeFfGgHhIiJjKkLlMmNnOoPpQqRrSsTtUuVvWwXxYyZz:();[]!.,-?";
 
 pub const game_name:&'static str="A Visual Novel by Clomance";
 
@@ -137,10 +188,44 @@ pub const wallpaper_movement_scale:f32=16f32;
 
 pub static mut game_settings:GameSettings=GameSettings::new();
 
+/// Загруженные ресурсы по названиям.
+pub static mut assets:Assets=Assets::new();
+
 fn main(){
-    // unsafe{
-    //     game_settings=GameSettings::load();
-    // }
+    // Загрузка настроек (при ошибке остаются настройки по умолчанию)
+    match GameSettings::load(){
+        Ok(settings)=>unsafe{
+            game_settings=settings;
+        }
+        #[cfg(debug_assertions)]
+        Err(e)=>println!("{}: {}",settings_path,e),
+        #[cfg(not(debug_assertions))]
+        Err(_)=>{}
+    }
+
+    let resources=match Resources::open(resources_path,resources_pack_path){
+        Ok(resources)=>resources,
+        Err(e)=>{
+            #[cfg(debug_assertions)]
+            println!("{}: {}",resources_pack_path,e);
+            write(resource_errors_path,format!("{}: {}\n",resources_pack_path,e));
+            return
+        }
+    };
+
+    // Загрузка списка ресурсов (без него игру не запустить)
+    let manifest=match Manifest::load(&resources,manifest_path).and_then(|manifest|{
+        manifest.require(font_names,track_names,image_names)?;
+        Ok(manifest)
+    }){
+        Ok(manifest)=>manifest,
+        Err(e)=>{
+            #[cfg(debug_assertions)]
+            println!("{}",e);
+            write(resource_errors_path,format!("{}\n",e));
+            return
+        }
+    };
 
     // Подключение аудио системы
     let audio=Audio::default(AudioSettings::new()).unwrap();
@@ -149,12 +234,14 @@ fn main(){
     // Настройка и создание окна и загрузка функций OpenGL
     let (mut window,mut graphics)=match Window::new(|mut monitors,window_settings|{
         // Установка полноэкранного режима для нужного экрана
-        let monitor=1;//unsafe{game_settings.monitor};
+        let monitor=unsafe{game_settings.monitor};
         let monitor=if monitor<monitors.len(){
             monitors.remove(monitor)
         }
         else{
-            //game_settings.monitor=0;
+            unsafe{
+                game_settings.monitor=0;
+            }
             monitors.remove(0)
         };
 
@@ -163,7 +250,7 @@ fn main(){
 
         let fullscreen=cat_engine::glium::glutin::window::Fullscreen::Borderless(Some(monitor));
 
-        let icon=load_window_icon();
+        let icon=load_window_icon(&resources,&manifest.image("window_icon").unwrap().path);
 
         window_settings.general.initial_colour=Some(White);
 
@@ -186,9 +273,10 @@ fn main(){
         window_settings.pixel_fmt_req.hardware_accelerated=None;
 
 
-        window_settings.graphics_base_settings.texture.vertex_buffer_size=40usize;
+        // По четыре вершины на объект
+        window_settings.graphics_base_settings.texture.vertex_buffer_size=4usize*textured_object_limit;
         window_settings.graphics_base_settings.texture.vertex_buffer_offset=0usize;
-        window_settings.graphics_base_settings.texture.object_buffer_size=10usize;
+        window_settings.graphics_base_settings.texture.object_buffer_size=textured_object_limit;
 
 
         window_settings.graphics_base_settings.simple.vertex_buffer_size=100usize;
@@ -227,9 +315,11 @@ fn main(){
     );
     { // Загрузка иконки курсора мыши
         
-        let mouse_texture=Texture::from_path(cursor_icon,window.display()).unwrap();
+        let mouse_image=load_image(&resources,&manifest.image("cursor").unwrap().path,None);
+        let mouse_texture=Texture::from_image(&mouse_image,window.display()).unwrap();
         let mouse_texture_index=graphics.add_texture(mouse_texture);
-        let _mouse_cursor_icon=graphics.add_textured_object(&image_base,mouse_texture_index).unwrap();
+        let mouse_cursor_icon=graphics.add_textured_object(&image_base,mouse_texture_index).unwrap();
+        unsafe{assets.add_texture("cursor",mouse_cursor_icon)}.unwrap();
     }
     { // Создание текстуры чуть больше размера экрана
         // Создание тектуры для обоев
@@ -247,7 +337,8 @@ fn main(){
 
         let wallpaper_texture=Texture::empty([width as u32,height as u32],window.display()).unwrap();
         let wallpaper_texture_index=graphics.add_texture(wallpaper_texture);
-        let _wallpaper=graphics.add_textured_object(&image_base,wallpaper_texture_index).unwrap();
+        let wallpaper=graphics.add_textured_object(&image_base,wallpaper_texture_index).unwrap();
+        unsafe{assets.add_texture("wallpaper",wallpaper)}.unwrap();
     }
 
     image_base.set_rect(unsafe{[0f32,0f32,window_width,window_height]});
@@ -256,46 +347,92 @@ fn main(){
             unsafe{[window_width as u32,window_height as u32]},window.display()
         ).unwrap();
         let swipe_screen_texture_index=graphics.add_texture(swipe_screen_texture);
-        let _swipe_screen_index=graphics.add_textured_object(&image_base,swipe_screen_texture_index).unwrap();
+        let swipe_screen=graphics.add_textured_object(&image_base,swipe_screen_texture_index).unwrap();
+        unsafe{assets.add_texture("swipe_screen",swipe_screen)}.unwrap();
     }
 
     // Данные для начальной загрузки
     let mut main_data=LoadingMainData::new();
 
     // Создание и запуск страницы загрузки
-    if let Game::Exit=LoadingScreen::new(&window,&mut graphics).run(&mut window,&mut graphics,&audio,&mut main_data){
+    if let Game::Exit=LoadingScreen::new(&resources,&manifest,&window,&mut graphics).run(&mut window,&mut graphics,&audio,&mut main_data){
         return
     }
 
-    // Загрузка треков в хранилище
-    for (track,name) in main_data.audio.into_iter().zip(audio_tracks_names.iter()){
-        audio.push_track(track,name.to_string());
+    // Регистрация загруженных ресурсов
+    if let Err(e)=register_assets(&mut main_data,&mut audio){
+        #[cfg(debug_assertions)]
+        println!("{}",e);
+        write(resource_errors_path,format!("{}\n",e));
+        return
     }
 
-    // Запуск мелодии главной темы (повторять бесконечно)
-    audio.play_track("main_theme",0u32);
+    // История загружается и проверяется целиком при запуске,
+    // при ошибке игра не запускается
+    let story=match &main_data.story{
+        Some(Ok(story))=>story,
+        Some(Err(e))=>{
+            #[cfg(debug_assertions)]
+            println!("{}",e);
+            write(resource_errors_path,format!("{}\n",e));
+            return
+        }
+        None=>return,
+    };
+    remove_file(resource_errors_path);
 
-    let images=main_data.textures;
+    // Запуск мелодии главной темы (музыка повторяется бесконечно)
+    let repeats=match manifest.track("main_theme").unwrap().category{
+        TrackCategory::Music=>0u32,
+        TrackCategory::Sound|TrackCategory::Voice=>1u32,
+    };
+    let main_theme=unsafe{assets.track("main_theme")}.unwrap();
+    audio.play_track(unsafe{assets.track_name(main_theme)}.unwrap(),repeats);
 
     // Цикл игры
     'game:loop{
         // Главное меню
         match{
-            let mut menu=MainMenu::new(&window,&mut graphics,&images[0..2]);
+            let mut menu=MainMenu::new(&window,&mut graphics);
             menu.open(&mut window,SwipeDirection::Left,&mut graphics);
             menu.run(&mut window,&mut graphics,&mut audio)
         }{
             Game::Exit=>break 'game,
             _=>{}
         }
+
+        // Страница игры
+        let mut game=GamePage::new(story,&resources,&manifest,&window,&mut graphics);
+
+        if let Game::Exit=game.open(&mut window,SwipeDirection::Left,&mut graphics){
+            break 'game
+        }
+
+        match game.run(&mut window,&mut graphics,&audio){
+            Game::Exit=>break 'game,
+            // Сохранение прогресса при возврате в главное меню
+            _=>if let Err(e)=unsafe{game_settings.save()}{
+                #[cfg(debug_assertions)]
+                println!("{}: {}",settings_path,e);
+            }
+        }
+    }
+
+    // Сохранение настроек и прогресса
+    if let Err(e)=unsafe{game_settings.save()}{
+        #[cfg(debug_assertions)]
+        println!("{}: {}",settings_path,e);
     }
 }
 
 /// Данные при начальной загрузке.
 pub struct LoadingMainData{
     pub fonts:Option<Vec<FontOwner>>,
-    pub audio:Vec<ChanneledTrack>,
-    pub textures:Vec<RgbaImage>,
+    /// Треки с названиями из списка ресурсов и длительность озвучки
+    pub audio:Vec<(String,ChanneledTrack,Option<Duration>)>,
+    /// Картинки с названиями из списка ресурсов
+    pub images:Vec<(String,RgbaImage)>,
+    pub story:Option<Result<Story,StoryError>>,
 }
 
 impl LoadingMainData{
@@ -303,23 +440,68 @@ impl LoadingMainData{
         Self{
             fonts:None,
             audio:Vec::new(),
-            textures:Vec::new(),
+            images:Vec::new(),
+            story:None,
         }
     }
 }
 
+/// Регистрация загруженных ресурсов по названиям и проверка,
+/// что есть все ресурсы, используемые страницами.
+fn register_assets(main_data:&mut LoadingMainData,audio:&mut AudioWrapper)->Result<(),AssetError>{
+    unsafe{
+        // Шрифты добавлены в порядке `font_names`
+        for (index,&name) in font_names.iter().enumerate(){
+            assets.add_font(name,index)?;
+        }
+
+        // Загрузка треков в хранилище
+        for (name,track,length) in main_data.audio.drain(..){
+            match length{
+                Some(length)=>assets.add_voice(&name,length)?,
+                None=>assets.add_track(&name)?,
+            };
+            audio.push_track(track,name);
+        }
+
+        for (name,image) in main_data.images.drain(..){
+            assets.add_image(&name,image)?;
+        }
+
+        assets.require(texture_names,font_names,track_names,menu_image_names)
+    }
+}
+
 /// Загрузка иконки окна
-fn load_window_icon()->Icon{
-    let image=cat_engine::image::open("./resources/images/window_icon.png").unwrap();
+fn load_window_icon(resources:&Resources,name:&str)->Icon{
+    let image=open_image(resources,name);
     let vec=image.to_bytes();
     let (width,height)=image.dimensions();
 
     Icon::from_rgba(vec,width,height).unwrap()
 }
 
+/// Размер текстуры обоев (чуть больше размера экрана для движения за курсором).
+fn wallpaper_size()->[u32;2]{
+    unsafe{
+        let dx=window_width/(wallpaper_movement_scale*2f32);
+        let dy=window_height/(wallpaper_movement_scale*2f32);
+        let width=(window_width+2f32*dx).ceil();
+        let height=(window_height+2f32*dy).ceil();
+
+        [width as u32,height as u32]
+    }
+}
+
+/// Чтение изображения `name` из ресурсов.
+fn open_image(resources:&Resources,name:&str)->DynamicImage{
+    let data=resources.read(name).unwrap();
+    cat_engine::image::load_from_memory(&data).unwrap()
+}
+
 // Загрузка изображений
-fn load_image<P:AsRef<Path>>(path:P,size:Option<[u32;2]>)->RgbaImage{
-    let mut image=cat_engine::image::open(path).unwrap();
+fn load_image(resources:&Resources,name:&str,size:Option<[u32;2]>)->RgbaImage{
+    let mut image=open_image(resources,name);
 
     if let Some([width,height])=size{
         image=image.resize_exact(width,height,cat_engine::image::imageops::FilterType::Gaussian);
@@ -333,13 +515,39 @@ fn load_image<P:AsRef<Path>>(path:P,size:Option<[u32;2]>)->RgbaImage{
     }
 }
 
+/// Загрузка изображения персонажа с сохранением пропорций.
+fn load_character_image(resources:&Resources,name:&str,height:f32)->RgbaImage{
+    let mut image=open_image(resources,name);
+    let image_height=image.height() as f32;
+    let image_width=image.width() as f32;
+
+    let width=image_width*height/image_height;
+
+    image=image.resize_exact(width as u32,height as u32,cat_engine::image::imageops::FilterType::Gaussian);
+    if let DynamicImage::ImageRgba8(image)=image{
+        image
+    }
+    else{
+        image.into_rgba8()
+    }
+}
+
 fn get_swipe_texture<'a,'b>(graphics:&'a mut Graphics2D)->&'b Texture{
     unsafe{
-        let r=graphics.get_textured_object_texture(swipe_screen_index) as *mut Texture;
+        let swipe_screen=assets.texture("swipe_screen").unwrap();
+        let r=graphics.get_textured_object_texture(swipe_screen.index()) as *mut Texture;
         &*r
     }
 }
 
+/// Снимок картинки для переходов - последней отрисованной в неё страницы.
+fn read_swipe_texture(graphics:&mut Graphics2D)->RgbaImage{
+    let raw:cat_engine::glium::texture::RawImage2d<u8>=get_swipe_texture(graphics).0.read();
+    let image=RgbaImage::from_raw(raw.width,raw.height,raw.data.into_owned()).unwrap();
+    // Строки текстуры идут снизу вверх
+    cat_engine::image::imageops::flip_vertical(&image)
+}
+
 fn draw_on_texture<F:FnOnce(&mut Graphics<SimpleFrameBuffer>)>(
     texture:&Texture,
     window:&Window,
@@ -359,60 +567,26 @@ fn draw_on_texture<F:FnOnce(&mut Graphics<SimpleFrameBuffer>)>(
 
 fn make_screenshot(window:&Window,audio:&AudioWrapper){
     unsafe{
-        audio.play_track("screenshot",1u32);
+        let screenshot_sound=assets.track("screenshot").unwrap();
+        audio.play_track(assets.track_name(screenshot_sound).unwrap(),1u32);
         let path=format!("screenshots/screenshot{}.png",game_settings.screenshot);
         game_settings.screenshot+=1;
         window.save_screenshot(path);
     }
 }
 
-// Загрузка фонов
-// fn load_wallpapers_textures_paths<P:AsRef<Path>+Clone>(path:P)->Vec<PathBuf>{
-//     let meta=metadata(path.clone()).unwrap();
-//     let mut textures=Vec::with_capacity(meta.len() as usize);
-//     let dir=read_dir(path).unwrap();
-
-//     for r in dir{
-//         let file=r.unwrap();
-//         let path=file.path();
-//         textures.push(path)
-//     }
-
-//     textures
-// }
-
-// fn load_characters_textures(height:f32)->Vec<RgbaImage>{
-//     let path="./resources/images/characters";
-//     let meta=metadata(path).unwrap();
+#[cfg(test)]
+mod tests{
+    use super::*;
 
-//     let mut char_textures=Vec::with_capacity(meta.len() as usize);
+    /// Ресурсы игры проходят проверки, выполняемые при запуске.
+    #[test]
+    fn game_resources(){
+        let resources=Resources::open(resources_path,resources_pack_path).unwrap();
 
-//     let dir=read_dir(path).unwrap();
+        let manifest=Manifest::load(&resources,manifest_path).unwrap();
+        manifest.require(font_names,track_names,image_names).unwrap();
 
-//     for r in dir{
-//         let file=r.unwrap();
-//         let _name=file.file_name();
-//         let path=file.path();
-//         let image=load_character_image(path,height);
-//         char_textures.push(image)
-//     }
-
-//     char_textures
-// }
-
-// Загрузка изображений
-// fn load_character_image<P:AsRef<Path>>(path:P,height:f32)->RgbaImage{
-//     let mut image=image::open(path).unwrap();
-//     let image_height=image.height() as f32;
-//     let image_width=image.width() as f32;
-
-//     let width=image_width*height/image_height;
-
-//     image=image.resize_exact(width as u32,height as u32,FilterType::Gaussian);
-//     if let DynamicImage::ImageRgba8(image)=image{
-//         image
-//     }
-//     else{
-//         image.into_rgba()
-//     }
-// }
\ No newline at end of file
+        Story::load(&resources,&story_paths).unwrap();
+    }
+}
@@ -0,0 +1,419 @@
+use crate::{
+    // consts
+    swipe_updates,
+    // statics
+    assets,
+    // enums
+    Game,
+    // functions
+    get_swipe_texture,
+    draw_on_texture,
+    make_screenshot,
+};
+
+use super::{
+    button_pressed,
+    settings::page_colour,
+};
+
+use lib::{
+    Align,
+    AlignX,
+    AlignY,
+    colours::{White,Gray,Light_blue},
+    user_interface::{
+        Button,
+        ButtonSettings,
+        TextView,
+        TextViewSettings,
+        EditTextView,
+        EditTextViewSettings,
+        GeneralSettings,
+        wrap_text,
+        fit_text,
+    },
+};
+
+use cat_engine::{
+    // statics
+    mouse_cursor,
+    window_height,
+    window_width,
+    // enums
+    KeyboardButton,
+    // structs
+    Window,
+    WindowEvent,
+    MouseButton,
+    graphics::{Graphics,Graphics2D},
+    audio::AudioWrapper,
+
+    glium::{
+        Surface,
+        glutin::event::MouseScrollDelta,
+    },
+};
+
+/// Отступ сверху до строки поиска и до списка.
+const search_top:f32=80f32;
+const list_top:f32=140f32;
+/// Отступ снизу, оставляемый под кнопку "Назад".
+const list_bottom:f32=80f32;
+
+const row_font_size:f32=24f32;
+/// Высота строки списка: шрифт и промежуток между строками.
+const row_height:f32=row_font_size+12f32;
+
+/// Строка, показанная в игре: имя говорящего и текст с подстановками.
+#[derive(Clone)]
+pub struct BacklogLine{
+    pub name:String,
+    pub text:String,
+}
+
+/// История реплик с поиском.
+pub struct BacklogPage{
+    lines:Vec<BacklogLine>,
+    // Строки списка для найденных реплик: имя (только в первой строке реплики)
+    // и часть текста, помещающаяся в ширину списка
+    rows:Vec<(String,String)>,
+    // Номер первой показанной строки списка
+    scroll:usize,
+    // Ширина имени и текста, правый край имени
+    name_width:f32,
+    text_width:f32,
+    name_right:f32,
+
+    header:TextView,
+    search_label:TextView,
+    search:EditTextView,
+    names:Vec<TextView>,
+    texts:Vec<TextView>,
+    escape:Button,
+    escape_pressed:bool,
+}
+
+impl BacklogPage{
+    pub fn new(lines:Vec<BacklogLine>,graphics:&mut Graphics2D)->BacklogPage{
+        let dialogue_font=unsafe{assets.font("dialogue")}.unwrap().index();
+
+        let header_settings=TextViewSettings::new("История",GeneralSettings::new(unsafe{[0f32,0f32,window_width,search_top]}))
+                .font_size(40f32)
+                .text_colour(White);
+        let header=TextView::new(header_settings,graphics);
+
+        let search_rect=unsafe{[window_width/4f32,search_top,window_width/2f32,40f32]};
+        let search_label_rect=[search_rect[0]-160f32,search_rect[1],150f32,search_rect[3]];
+        let search_label_settings=TextViewSettings::new("Поиск:",GeneralSettings::new(search_label_rect))
+                .font_size(row_font_size)
+                .text_colour(White)
+                .align_x(AlignX::Right);
+        let search_label=TextView::new(search_label_settings,graphics);
+
+        let search_settings=EditTextViewSettings::new("",search_rect)
+                .capacity(64)
+                .align(Align{x:AlignX::Left,y:AlignY::Center});
+        let search=EditTextView::new(search_settings,graphics);
+
+        // Строки списка: имя слева, текст справа.
+        // Длинные реплики занимают несколько строк
+        let rows=unsafe{((window_height-list_top-list_bottom)/row_height) as usize};
+        let name_width=unsafe{window_width/5f32};
+        let text_width=unsafe{window_width-name_width-40f32};
+        let mut names=Vec::with_capacity(rows);
+        let mut texts=Vec::with_capacity(rows);
+
+        for row in 0..rows{
+            let y=list_top+row as f32*row_height;
+
+            let name_rect=[20f32,y,name_width-20f32,row_height];
+            let name_settings=TextViewSettings::new("",GeneralSettings::new(name_rect))
+                    .font(dialogue_font)
+                    .font_size(row_font_size)
+                    .text_colour(Gray)
+                    .align_x(AlignX::Right)
+                    .align_y(AlignY::Down);
+            names.push(TextView::new(name_settings,graphics));
+
+            let text_rect=[name_width+20f32,y,text_width,row_height];
+            let text_settings=TextViewSettings::new("",GeneralSettings::new(text_rect))
+                    .font(dialogue_font)
+                    .font_size(row_font_size)
+                    .text_colour(White)
+                    .align_x(AlignX::Left)
+                    .align_y(AlignY::Down);
+            texts.push(TextView::new(text_settings,graphics));
+        }
+
+        let escape_rect=unsafe{[
+            10f32,
+            window_height-70f32,
+            160f32,
+            60f32,
+        ]};
+        let escape=Button::new(ButtonSettings::new("Назад",escape_rect),graphics);
+
+        let mut page=Self{
+            lines,
+            rows:Vec::new(),
+            scroll:0,
+            name_width:name_width-20f32,
+            text_width,
+            name_right:name_width,
+
+            header,
+            search_label,
+            search,
+            names,
+            texts,
+            escape,
+            escape_pressed:false,
+        };
+
+        page.find(graphics);
+
+        page
+    }
+
+    /// Переход с предыдущей страницы (картинка `swipe_screen`) справа налево.
+    pub fn open(&mut self,window:&mut Window,graphics:&mut Graphics2D)->Game{
+        let swipe_screen=unsafe{assets.texture("swipe_screen")}.unwrap();
+
+        let mut result=Game::Next;
+
+        let mut frames=0u8;
+
+        let mut shift=0f32;
+
+        let dshift=unsafe{window_width/swipe_updates as f32};
+
+        window.run(|window,event|{
+            match event{
+                WindowEvent::CloseRequested=>result=Game::Exit,
+                WindowEvent::Update=>{
+                    frames+=1;
+                    if frames==swipe_updates{
+                        window.stop_events();
+                    }
+                    else{
+                        shift-=dshift;
+                    }
+                }
+
+                WindowEvent::RedrawRequested=>{
+                    let next_page_shift=unsafe{window_width+shift};
+
+                    window.draw(&graphics,|graphics|{
+                        graphics.clear_colour(page_colour);
+
+                        graphics.draw_shift_textured_object(swipe_screen.index(),[shift,0f32]);
+
+                        self.draw_shift([next_page_shift,0f32],graphics);
+                    });
+                }
+
+                _=>{}
+            }
+        });
+
+        result
+    }
+
+    pub fn run(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Game{
+        let cursor=unsafe{assets.texture("cursor")}.unwrap();
+        let button_sound=unsafe{assets.track("button_pressed")}.unwrap();
+
+        let mut result=Game::Next;
+
+        window.run(|window,event|{
+            match event{
+                WindowEvent::CloseRequested=>result=Game::Exit,
+
+                WindowEvent::RedrawRequested=>{
+                    let [dx,dy]=unsafe{mouse_cursor.center_radius()};
+                    window.draw(graphics,|graphics|{
+                        // Фон
+                        graphics.clear_colour(page_colour);
+
+                        self.draw_shift([0f32;2],graphics);
+
+                        // Отрисовка курсора
+                        graphics.draw_shift_textured_object(cursor.index(),[dx,dy]).unwrap();
+                    }).unwrap();
+                }
+
+                WindowEvent::MousePressed(button)=>{
+                    if let MouseButton::Left=button{
+                        let [x,y]=unsafe{mouse_cursor.position()};
+
+                        if self.escape.pressed(x,y){
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                            *graphics.get_simple_object_colour(self.escape.background_index())=button_pressed;
+                            self.escape_pressed=true;
+                        }
+                    }
+                }
+
+                WindowEvent::MouseReleased(button)=>{
+                    if let MouseButton::Left=button{
+                        if self.escape_pressed{
+                            self.escape_pressed=false;
+
+                            let [x,y]=unsafe{mouse_cursor.position()};
+                            *graphics.get_simple_object_colour(self.escape.background_index())=Light_blue;
+
+                            if self.escape.released(x,y){
+                                window.stop_events();
+                            }
+                        }
+                    }
+                }
+
+                WindowEvent::MouseScroll(delta)=>{
+                    let up=match delta{
+                        MouseScrollDelta::LineDelta(_,dy)=>dy>0f32,
+                        MouseScrollDelta::PixelDelta(delta)=>delta.y>0f64,
+                    };
+
+                    if up{
+                        self.scroll_up(graphics);
+                    }
+                    else{
+                        self.scroll_down(graphics);
+                    }
+                }
+
+                WindowEvent::CharacterInput(character)=>if !character.is_control(){
+                    self.search.push_char(character,graphics);
+                    self.find(graphics);
+                }
+
+                WindowEvent::KeyboardPressed(button)=>match button{
+                    KeyboardButton::Escape=>{
+                        window.stop_events();
+                    }
+
+                    KeyboardButton::Backspace=>{
+                        self.search.pop_char(graphics);
+                        self.find(graphics);
+                    }
+
+                    KeyboardButton::Up=>self.scroll_up(graphics),
+
+                    KeyboardButton::Down=>self.scroll_down(graphics),
+
+                    KeyboardButton::F5=>make_screenshot(window,audio),
+
+                    _=>{}
+                }
+
+                _=>{}
+            }
+        });
+
+        self.render_to_texture(window,graphics);
+
+        self.remove_objects(graphics);
+
+        result
+    }
+
+    /// Отбор строк по тексту поиска (без учёта регистра),
+    /// разбиение их на строки списка и прокрутка к последней найденной.
+    fn find(&mut self,graphics:&mut Graphics2D){
+        let query=self.search.text(graphics).to_lowercase();
+
+        // У всех строк списка один шрифт и размер
+        let font=*graphics.get_text_object_font(self.texts[0].index());
+        let scale=*graphics.get_text_object_scale(self.texts[0].index());
+        let font=graphics.get_font(font);
+        let width=|text:&str|font.text_width(text,scale);
+
+        self.rows.clear();
+        for line in &self.lines{
+            let found=query.is_empty()
+                || line.name.to_lowercase().contains(&query)
+                || line.text.to_lowercase().contains(&query);
+            if !found{
+                continue
+            }
+
+            let name=fit_text(&line.name,self.name_width,width);
+            for (row,text) in wrap_text(&line.text,self.text_width,width).into_iter().enumerate(){
+                let name=if row==0{name.clone()}else{String::new()};
+                self.rows.push((name,text));
+            }
+        }
+
+        self.scroll=self.rows.len().saturating_sub(self.texts.len());
+
+        self.update_rows(graphics);
+    }
+
+    fn scroll_up(&mut self,graphics:&mut Graphics2D){
+        if self.scroll>0{
+            self.scroll-=1;
+            self.update_rows(graphics);
+        }
+    }
+
+    fn scroll_down(&mut self,graphics:&mut Graphics2D){
+        if self.scroll+self.texts.len()<self.rows.len(){
+            self.scroll+=1;
+            self.update_rows(graphics);
+        }
+    }
+
+    /// Вывод строк списка, начиная с `scroll`.
+    fn update_rows(&mut self,graphics:&mut Graphics2D){
+        for row in 0..self.texts.len(){
+            let (name,text)=match self.rows.get(self.scroll+row){
+                Some((name,text))=>(name.clone(),text.clone()),
+                None=>(String::new(),String::new()),
+            };
+
+            // Имя выравнивается по правому краю своей ширины
+            let index=self.names[row].index();
+            let font=*graphics.get_text_object_font(index);
+            let scale=*graphics.get_text_object_scale(index);
+            let name_width=graphics.get_font(font).text_width(&name,scale);
+            graphics.get_text_object_position(index)[0]=self.name_right-name_width;
+
+            *graphics.get_text_object_text(index)=name;
+            *graphics.get_text_object_text(self.texts[row].index())=text;
+        }
+    }
+
+    fn draw_shift<S:Surface>(&self,shift:[f32;2],graphics:&mut Graphics<S>){
+        self.header.draw_shift(shift,graphics);
+        self.search_label.draw_shift(shift,graphics);
+        self.search.draw_shift(shift,graphics);
+
+        for row in 0..self.texts.len(){
+            self.names[row].draw_shift(shift,graphics);
+            self.texts[row].draw_shift(shift,graphics);
+        }
+
+        self.escape.draw_shift(shift,graphics);
+    }
+
+    fn render_to_texture(&self,window:&Window,graphics:&mut Graphics2D){
+        let swipe_screen_texture=get_swipe_texture(graphics);
+
+        draw_on_texture(&swipe_screen_texture,window,graphics,|graphics|{
+            graphics.clear_colour(page_colour);
+
+            self.draw_shift([0f32;2],graphics);
+        });
+    }
+
+    fn remove_objects(&mut self,graphics:&mut Graphics2D){
+        // Заголовок, поиск, строки списка и кнопка "Назад"
+        for _ in 0..2*self.texts.len()+4{
+            graphics.remove_last_text_object();
+        }
+        // Поле поиска и кнопка "Назад"
+        graphics.remove_last_simple_object();
+        graphics.remove_last_simple_object();
+    }
+}
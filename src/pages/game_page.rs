@@ -0,0 +1,1195 @@
+use crate::{
+    // consts
+    wallpaper_movement_scale,
+    swipe_updates,
+    // statics
+    game_settings,
+    assets,
+    // enums
+    Game,
+    // structs
+    assets::TrackHandle,
+    character_stage::CharacterStage,
+    saves::{SaveSlot,quick_save_path},
+    // functions
+    load_image,
+    wallpaper_size,
+    get_swipe_texture,
+    draw_on_texture,
+    read_swipe_texture,
+    make_screenshot,
+};
+
+use super::{
+    button_pressed,
+    SwipeDirection,
+    SaveSlotsPage,
+    SlotsMode,
+    BacklogPage,
+    BacklogLine,
+    PauseMenu,
+};
+
+use lib::{
+    AlignX,
+    AlignY,
+    colours::{White,Light_blue},
+    user_interface::{
+        Menu,
+        MenuSettings,
+        GeneralSettings,
+        TextView,
+        TextViewSettings,
+        Typewriter,
+        TypewriterSettings,
+    },
+    story::{
+        Story,
+        Dialogue,
+        Step,
+        Character,
+        Speaker,
+        Condition,
+        TextTemplate,
+        Bookmark,
+        Resolved,
+        Variables,
+    },
+    manifest::Manifest,
+    resources::Resources,
+};
+
+use cat_engine::{
+    // statics
+    mouse_cursor,
+    window_height,
+    window_width,
+    // enums
+    KeyboardButton,
+    // structs
+    Window,
+    WindowEvent,
+    MouseButton,
+    graphics::{Graphics,Graphics2D},
+    texture::{ImageBase,Texture},
+    audio::AudioWrapper,
+
+    glium::{
+        Surface,
+        glutin::event::MouseScrollDelta,
+    },
+};
+
+use std::{
+    collections::VecDeque,
+    time::{Instant,Duration},
+};
+
+const name_font_size:f32=32f32;
+const text_font_size:f32=28f32;
+
+/// Отступ текста от края окна диалога.
+const dialogue_box_margin:f32=40f32;
+
+const choice_button_height:f32=60f32;
+const choice_font_size:f32=26f32;
+
+const notice_font_size:f32=24f32;
+/// Время показа уведомления в обновлениях (50 в секунду).
+const notice_updates:u32=100;
+
+/// Задержка авточтения перед переходом в секундах, к ней добавляется время чтения строки.
+const auto_base_delay:f32=1f32;
+
+/// Обновлений на строку в режиме пропуска.
+const skip_interval:u8=3;
+
+/// Наибольшее количество шагов отката.
+const rollback_limit:usize=100;
+
+/// Диалог до загрузки первой страницы.
+static empty_dialogue:Dialogue=Dialogue::empty;
+
+/// Состояние истории на показанной строке для отката.
+/// Обои восстанавливаются по странице.
+struct Snapshot{
+    page:usize,
+    line:usize,
+    variables:Variables,
+    // Количество сделанных выборов и строк истории реплик
+    choices:usize,
+    backlog:usize,
+    // Выведенные имя, реплика и говорящий (при выборе - от предыдущей строки)
+    name:String,
+    text:String,
+    speaker:Speaker,
+    // Количество выведенных символов реплики
+    shown:usize,
+    // Персонажи на экране
+    characters:Vec<Character>,
+}
+
+/// Страница игры - обои, персонажи, окно диалога, имя говорящего и его реплика.
+pub struct GamePage<'a>{
+    story:&'a Story,
+    resources:&'a Resources,
+    page:usize,
+    dialogue:&'a Dialogue,
+    line:usize,
+    // Название текущих обоев
+    wallpaper:String,
+    finished:bool,
+
+    dialogue_box:usize,
+    dialogue_box_y:f32,
+    stage:CharacterStage,
+    name:TextView,
+    text:TextView,
+    typewriter:Typewriter,
+    // Варианты текущего выбора
+    choice:Option<Menu>,
+    // Номера показанных вариантов в шаге выбора
+    choice_options:Vec<usize>,
+    // Начало ещё не учтённого времени игры
+    started:Instant,
+    // Уведомление о быстром сохранении/загрузке и оставшееся время его показа
+    notice:TextView,
+    notice_updates:u32,
+    // Пройденные строки для отката, последняя - в конце
+    history:VecDeque<Snapshot>,
+    // Показанные за время игры строки
+    backlog:Vec<BacklogLine>,
+    // Текущая строка была прочитана до показа
+    line_read:bool,
+    // Режим пропуска и обновления с последнего пропуска строки
+    skipping:bool,
+    skip_updates:u8,
+    // Авточтение, его значок в окне диалога
+    // и обновления с окончания вывода строки
+    auto:bool,
+    auto_indicator:TextView,
+    auto_updates:u32,
+    // Озвучка текущей строки, ещё не запущенная,
+    // и время окончания запущенной
+    voice:Option<TrackHandle>,
+    voice_end:Option<Instant>,
+}
+
+impl<'a> GamePage<'a>{
+    /// `story` - история, проверенная при запуске игры.
+    pub fn new(story:&'a Story,resources:&'a Resources,manifest:&Manifest,window:&Window,graphics:&mut Graphics2D)->GamePage<'a>{
+        let dialogue_font=unsafe{assets.font("dialogue")}.unwrap().index();
+
+        unsafe{
+            game_settings.variables.declare(story.declarations());
+        }
+
+        // Окно диалога (высота по пропорциям картинки)
+        let dialogue_box_image=load_image(resources,&manifest.image("dialogue_box").unwrap().path,None);
+        let dialogue_box_texture=Texture::from_image(&dialogue_box_image,window.display()).unwrap();
+        let (box_width,box_height)=dialogue_box_texture.0.dimensions();
+        let box_height=unsafe{window_width*box_height as f32/box_width as f32};
+        let box_y=unsafe{window_height-box_height};
+
+        let dialogue_box_texture=graphics.add_texture(dialogue_box_texture);
+        let dialogue_box_base=ImageBase::new(unsafe{[0f32,box_y,window_width,box_height]},White);
+        let dialogue_box=graphics.add_textured_object(&dialogue_box_base,dialogue_box_texture).unwrap();
+
+        // Имя говорящего (текст выравнивается по нижней границе области)
+        let name_rect=unsafe{[
+            dialogue_box_margin,
+            box_y,
+            window_width-2f32*dialogue_box_margin,
+            box_height*0.35f32,
+        ]};
+        let name_settings=TextViewSettings::new("",GeneralSettings::new(name_rect))
+                .font(dialogue_font)
+                .font_size(name_font_size)
+                .text_colour(White)
+                .align_x(AlignX::Left)
+                .align_y(AlignY::Down);
+
+        // Реплика
+        let text_rect=unsafe{[
+            dialogue_box_margin,
+            box_y,
+            window_width-2f32*dialogue_box_margin,
+            box_height*0.7f32,
+        ]};
+        let text_settings=TextViewSettings::new("",GeneralSettings::new(text_rect))
+                .font(dialogue_font)
+                .font_size(text_font_size)
+                .text_colour(White)
+                .align_x(AlignX::Left)
+                .align_y(AlignY::Down);
+
+        let text=TextView::new(text_settings,graphics);
+
+        // Уведомление в правом верхнем углу
+        let notice_rect=unsafe{[
+            window_width/2f32,
+            dialogue_box_margin/2f32,
+            window_width/2f32-dialogue_box_margin,
+            dialogue_box_margin,
+        ]};
+        let notice_settings=TextViewSettings::new("",GeneralSettings::new(notice_rect))
+                .font_size(notice_font_size)
+                .text_colour(White)
+                .align_x(AlignX::Right)
+                .align_y(AlignY::Center);
+        let notice=TextView::new(notice_settings,graphics);
+
+        // Значок авточтения в правом верхнем углу окна диалога
+        let auto_indicator_rect=unsafe{[
+            window_width/2f32,
+            box_y,
+            window_width/2f32-dialogue_box_margin,
+            box_height*0.35f32,
+        ]};
+        let auto_indicator_settings=TextViewSettings::new("Авто",GeneralSettings::new(auto_indicator_rect))
+                .font(dialogue_font)
+                .font_size(notice_font_size)
+                .text_colour(White)
+                .align_x(AlignX::Right)
+                .align_y(AlignY::Down);
+        let auto_indicator=TextView::new(auto_indicator_settings,graphics);
+        let typewriter=Typewriter::new(text.index(),TypewriterSettings::new(unsafe{game_settings.signs_per_frame}));
+
+        let mut page=Self{
+            story,
+            resources,
+            page:0,
+            dialogue:&empty_dialogue,
+            line:0,
+            wallpaper:String::new(),
+            finished:false,
+
+            dialogue_box,
+            dialogue_box_y:box_y,
+            stage:CharacterStage::new(),
+            name:TextView::new(name_settings,graphics),
+            text,
+            typewriter,
+            choice:None,
+            choice_options:Vec::new(),
+            started:Instant::now(),
+            notice,
+            notice_updates:0,
+            history:VecDeque::new(),
+            backlog:Vec::new(),
+            line_read:false,
+            skipping:false,
+            skip_updates:0,
+            auto:false,
+            auto_indicator,
+            auto_updates:0,
+            voice:None,
+            voice_end:None,
+        };
+
+        if !page.go_to_saved(window,graphics){
+            page.finish();
+        }
+
+        page
+    }
+
+    /// Переход с предыдущей страницы (картинка `swipe_screen`).
+    pub fn open(&mut self,window:&mut Window,swipe_direction:SwipeDirection,graphics:&mut Graphics2D)->Game{
+        let swipe_screen=unsafe{assets.texture("swipe_screen")}.unwrap();
+
+        let mut result=Game::Next;
+
+        let mut frames=0u8;
+
+        let mut current_page_shift=[0f32;2];
+
+        // Начальный сдвиг этой страницы и сдвиг за кадр
+        let (mut next_page_shift,dshift)=unsafe{
+            match swipe_direction{
+                SwipeDirection::Up=>([0f32,window_height],[0f32,-window_height/swipe_updates as f32]),
+                SwipeDirection::Down=>([0f32,-window_height],[0f32,window_height/swipe_updates as f32]),
+                SwipeDirection::Left=>([window_width,0f32],[-window_width/swipe_updates as f32,0f32]),
+                SwipeDirection::Right=>([-window_width,0f32],[window_width/swipe_updates as f32,0f32]),
+            }
+        };
+
+        window.run(|window,event|{
+            match event{
+                WindowEvent::CloseRequested=>result=Game::Exit,
+                WindowEvent::Update=>{
+                    frames+=1;
+                    if frames==swipe_updates{
+                        window.stop_events();
+                    }
+                    else{
+                        current_page_shift[0]+=dshift[0];
+                        current_page_shift[1]+=dshift[1];
+
+                        next_page_shift[0]+=dshift[0];
+                        next_page_shift[1]+=dshift[1];
+                    }
+                }
+
+                WindowEvent::RedrawRequested=>{
+                    window.draw(&graphics,|graphics|{
+                        graphics.draw_shift_textured_object(swipe_screen.index(),current_page_shift);
+
+                        self.draw_shift(next_page_shift,graphics);
+                    });
+                }
+
+                _=>{}
+            }
+        });
+
+        result
+    }
+
+    pub fn run(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Game{
+        let cursor=unsafe{assets.texture("cursor")}.unwrap();
+        let button_sound=unsafe{assets.track("button_pressed")}.unwrap();
+
+        let mut result=Game::Next;
+
+        self.started=Instant::now();
+
+        if !self.finished{
+            window.run(|window,event|{
+                match event{
+                    WindowEvent::CloseRequested=>result=Game::Exit,
+
+                    WindowEvent::Update=>{
+                        self.typewriter.update(graphics);
+                        self.notice_updates=self.notice_updates.saturating_sub(1);
+                        self.play_voice(audio);
+
+                        if self.skipping{
+                            if let Some(game)=self.skip(window,graphics){
+                                result=game;
+                                window.stop_events();
+                            }
+                        }
+                        else if self.auto{
+                            if let Some(game)=self.auto_advance(window,graphics){
+                                result=game;
+                                window.stop_events();
+                            }
+                        }
+                    }
+
+                    WindowEvent::RedrawRequested=>{
+                        let [dx,dy]=unsafe{mouse_cursor.center_radius()};
+
+                        window.draw(graphics,|graphics|{
+                            self.draw_shift([0f32;2],graphics);
+
+                            if self.notice_updates!=0{
+                                self.notice.draw(graphics);
+                            }
+
+                            if self.auto{
+                                self.auto_indicator.draw(graphics);
+                            }
+
+                            // Отрисовка курсора
+                            graphics.draw_shift_textured_object(cursor.index(),[dx,dy]).unwrap();
+                        }).unwrap();
+                    }
+
+                    WindowEvent::MousePressed(button)=>{
+                        if let MouseButton::Left=button{
+                            if let Some(choice)=&mut self.choice{
+                                let [x,y]=unsafe{mouse_cursor.position()};
+                                if let Some(button)=choice.pressed(x,y){
+                                    audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                                    *graphics.get_simple_object_colour(choice.button_index(button))=button_pressed;
+                                }
+                            }
+                            else if let Some(game)=self.click(window,graphics){
+                                result=game;
+                                window.stop_events();
+                            }
+                        }
+                    }
+
+                    WindowEvent::MouseReleased(button)=>{
+                        if let MouseButton::Left=button{
+                            if let Some(choice)=&mut self.choice{
+                                if let Some(pressed_button)=choice.pressed_button(){
+                                    let [x,y]=unsafe{mouse_cursor.position()};
+                                    *graphics.get_simple_object_colour(choice.button_index(pressed_button))=Light_blue;
+
+                                    if let Some(option)=choice.released(x,y){
+                                        if let Some(game)=self.choose(option,window,graphics){
+                                            result=game;
+                                            window.stop_events();
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Откат колесом мыши вверх
+                    WindowEvent::MouseScroll(delta)=>{
+                        let up=match delta{
+                            MouseScrollDelta::LineDelta(_,dy)=>dy>0f32,
+                            MouseScrollDelta::PixelDelta(delta)=>delta.y>0f64,
+                        };
+
+                        if up{
+                            if let Some(game)=self.rollback(window,graphics){
+                                result=game;
+                                window.stop_events();
+                            }
+                        }
+                    }
+
+                    WindowEvent::KeyboardPressed(button)=>match button{
+                        KeyboardButton::Space=>{
+                            if let Some(game)=self.click(window,graphics){
+                                result=game;
+                                window.stop_events();
+                            }
+                        }
+
+                        KeyboardButton::Escape=>if let Some(game)=self.open_pause(window,graphics,audio){
+                            result=game;
+                            window.stop_events();
+                        }
+
+                        KeyboardButton::Tab=>if self.skipping{
+                            self.stop_skipping();
+                        }
+                        else{
+                            self.start_skipping(graphics);
+                        }
+
+                        KeyboardButton::F7=>{
+                            self.auto=!self.auto;
+                            self.auto_updates=0;
+                        }
+
+                        KeyboardButton::Backspace=>if let Some(game)=self.rollback(window,graphics){
+                            result=game;
+                            window.stop_events();
+                        }
+
+                        KeyboardButton::F2=>if let Some(game)=self.open_slots(true,window,graphics,audio){
+                            result=game;
+                            window.stop_events();
+                        }
+
+                        KeyboardButton::F3=>if let Some(game)=self.open_slots(false,window,graphics,audio){
+                            result=game;
+                            window.stop_events();
+                        }
+
+                        KeyboardButton::F4=>if let Some(game)=self.open_backlog(window,graphics,audio){
+                            result=game;
+                            window.stop_events();
+                        }
+
+                        KeyboardButton::F5=>make_screenshot(window,audio),
+
+                        KeyboardButton::F6=>self.quick_save(window,graphics),
+
+                        KeyboardButton::F9=>if let Some(game)=self.quick_load(window,graphics){
+                            result=game;
+                            window.stop_events();
+                        }
+
+                        _=>{}
+                    }
+
+                    _=>{}
+                }
+            });
+        }
+        else{
+            result=Game::MainMenu;
+        }
+
+        self.update_playtime();
+
+        // Автосохранение при закрытии окна
+        if let Game::Exit=result{
+            self.autosave(window,graphics);
+        }
+
+        self.render_to_texture(window,graphics);
+
+        self.remove_objects(graphics);
+
+        result
+    }
+
+    /// Учёт времени игры с начала страницы или предыдущего учёта.
+    fn update_playtime(&mut self){
+        let seconds=self.started.elapsed().as_secs();
+        self.started+=Duration::from_secs(seconds);
+        unsafe{
+            game_settings.playtime+=seconds;
+        }
+    }
+
+    /// Страница слотов сохранения (`save`) или загрузки.
+    /// При загрузке игра продолжается с загруженного места.
+    fn open_slots(&mut self,save:bool,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Option<Game>{
+        self.stop_skipping();
+        self.update_playtime();
+        self.render_to_texture(window,graphics);
+
+        let mode=if save{
+            SlotsMode::Save{
+                screen:read_swipe_texture(graphics),
+            }
+        }
+        else{
+            SlotsMode::Load
+        };
+
+        let mut slots=SaveSlotsPage::new(mode,window,graphics);
+        slots.open(window,graphics);
+        let result=slots.run(window,graphics,audio);
+
+        let next=match result{
+            Game::Exit=>return Some(Game::Exit),
+            Game::Load=>self.load_saved(window,graphics),
+            _=>true,
+        };
+
+        self.open(window,SwipeDirection::Right,graphics);
+
+        self.moved(next)
+    }
+
+    /// Меню паузы поверх остановленной сцены.
+    fn open_pause(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Option<Game>{
+        self.stop_skipping();
+        self.update_playtime();
+        self.render_to_texture(window,graphics);
+
+        let mut pause=PauseMenu::new(read_swipe_texture(graphics),window,graphics);
+        let result=pause.run(window,graphics,audio);
+
+        // Время в меню паузы не учитывается
+        self.started=Instant::now();
+
+        match result{
+            Game::Exit=>Some(Game::Exit),
+            Game::MainMenu=>Some(Game::MainMenu),
+            Game::Load=>{
+                let next=self.load_saved(window,graphics);
+
+                self.open(window,SwipeDirection::Right,graphics);
+
+                self.moved(next)
+            }
+            _=>None,
+        }
+    }
+
+    /// Переход на место, загруженное на странице слотов.
+    fn load_saved(&mut self,window:&Window,graphics:&mut Graphics2D)->bool{
+        self.remove_choice(graphics);
+        unsafe{
+            game_settings.variables.declare(self.story.declarations());
+        }
+        self.go_to_saved(window,graphics)
+    }
+
+    /// Страница истории реплик.
+    fn open_backlog(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Option<Game>{
+        self.stop_skipping();
+        self.render_to_texture(window,graphics);
+
+        let mut backlog=BacklogPage::new(self.backlog.clone(),graphics);
+        if let Game::Exit=backlog.open(window,graphics){
+            return Some(Game::Exit)
+        }
+        if let Game::Exit=backlog.run(window,graphics,audio){
+            return Some(Game::Exit)
+        }
+
+        match self.open(window,SwipeDirection::Right,graphics){
+            Game::Exit=>Some(Game::Exit),
+            _=>None,
+        }
+    }
+
+    /// Текущее положение в игре со снимком экрана.
+    fn capture(&mut self,window:&Window,graphics:&mut Graphics2D)->SaveSlot{
+        self.update_playtime();
+        self.render_to_texture(window,graphics);
+
+        SaveSlot::capture(&read_swipe_texture(graphics))
+    }
+
+    /// Сохранение текущего положения в слот автосохранения.
+    fn autosave(&mut self,window:&Window,graphics:&mut Graphics2D){
+        if let Err(e)=self.capture(window,graphics).autosave(){
+            #[cfg(debug_assertions)]
+            println!("Автосохранение: {}",e);
+        }
+    }
+
+    /// Сохранение текущего положения в слот быстрого сохранения.
+    fn quick_save(&mut self,window:&Window,graphics:&mut Graphics2D){
+        let save=self.capture(window,graphics);
+
+        let notice=match save.save_file(quick_save_path){
+            Ok(())=>"Быстрое сохранение".to_string(),
+            Err(e)=>{
+                #[cfg(debug_assertions)]
+                println!("{}: {}",quick_save_path,e);
+                "Не удалось сохранить".to_string()
+            }
+        };
+
+        self.show_notice(notice,graphics);
+    }
+
+    /// Загрузка из слота быстрого сохранения без перехода на страницу слотов.
+    fn quick_load(&mut self,window:&Window,graphics:&mut Graphics2D)->Option<Game>{
+        self.stop_skipping();
+        let save=match SaveSlot::load_file(quick_save_path){
+            Ok(Some(save))=>save,
+            Ok(None)=>{
+                self.show_notice("Нет быстрого сохранения".to_string(),graphics);
+                return None
+            }
+            Err(e)=>{
+                #[cfg(debug_assertions)]
+                println!("{}: {}",quick_save_path,e);
+                self.show_notice("Не удалось загрузить".to_string(),graphics);
+                return None
+            }
+        };
+
+        self.remove_choice(graphics);
+        save.restore();
+        self.started=Instant::now();
+
+        unsafe{
+            game_settings.variables.declare(self.story.declarations());
+        }
+
+        self.show_notice("Быстрая загрузка".to_string(),graphics);
+
+        let next=self.go_to_saved(window,graphics);
+
+        self.moved(next)
+    }
+
+    fn show_notice(&mut self,notice:String,graphics:&mut Graphics2D){
+        *graphics.get_text_object_text(self.notice.index())=notice;
+        self.notice_updates=notice_updates;
+    }
+
+    /// "Далее": первое нажатие выводит реплику полностью, второе - переход к следующей.
+    /// Во время выбора не действует.
+    fn click(&mut self,window:&Window,graphics:&mut Graphics2D)->Option<Game>{
+        if self.skipping{
+            self.stop_skipping();
+            None
+        }
+        else if self.choice.is_some(){
+            None
+        }
+        else if self.typewriter.is_complete(){
+            self.remember(graphics);
+            self.next_line(window,graphics)
+        }
+        else{
+            self.typewriter.complete(graphics);
+            None
+        }
+    }
+
+    /// Включение режима пропуска.
+    fn start_skipping(&mut self,graphics:&mut Graphics2D){
+        self.skipping=true;
+        self.skip_updates=0;
+        self.show_notice("Пропуск".to_string(),graphics);
+    }
+
+    fn stop_skipping(&mut self){
+        if self.skipping{
+            self.skipping=false;
+            self.notice_updates=0;
+        }
+    }
+
+    /// Шаг режима пропуска: переход к следующей строке раз в `skip_interval` обновлений.
+    /// Пропуск останавливается на выборе и на непрочитанной строке,
+    /// если в настройках не выбран пропуск всех строк.
+    fn skip(&mut self,window:&Window,graphics:&mut Graphics2D)->Option<Game>{
+        // Уведомление показывается, пока идёт пропуск
+        self.notice_updates=notice_updates;
+
+        self.skip_updates+=1;
+        if self.skip_updates<skip_interval{
+            return None
+        }
+        self.skip_updates=0;
+
+        if self.choice.is_some(){
+            self.stop_skipping();
+            return None
+        }
+
+        self.typewriter.complete(graphics);
+        self.remember(graphics);
+        let next=self.next_line(window,graphics);
+
+        if self.choice.is_some() || !self.line_read && !unsafe{game_settings.skip_all}{
+            self.stop_skipping();
+        }
+
+        next
+    }
+
+    /// Шаг авточтения: переход к следующей строке после её вывода и задержки,
+    /// зависящей от длины строки и скорости из настроек.
+    /// На выборе авточтение ждёт игрока, на озвученной строке - конца озвучки,
+    /// и только после этого отсчитывается задержка.
+    fn auto_advance(&mut self,window:&Window,graphics:&mut Graphics2D)->Option<Game>{
+        if self.choice.is_some() || !self.typewriter.is_complete() || self.voice_playing(){
+            self.auto_updates=0;
+            return None
+        }
+
+        let characters=self.typewriter.text().chars().count() as f32;
+        let delay=auto_base_delay+characters/unsafe{game_settings.auto_speed.max(1f32)};
+
+        // 50 обновлений в секунду
+        self.auto_updates+=1;
+        if (self.auto_updates as f32)<delay*50f32{
+            return None
+        }
+        self.auto_updates=0;
+
+        self.remember(graphics);
+        self.next_line(window,graphics)
+    }
+
+    /// Запуск озвучки строки, показанной с прошлого обновления.
+    /// При пропуске строки не озвучиваются.
+    fn play_voice(&mut self,audio:&AudioWrapper){
+        let voice=match self.voice.take(){
+            Some(voice)=>voice,
+            None=>return,
+        };
+
+        if self.skipping{
+            return
+        }
+
+        unsafe{
+            audio.play_track(assets.track_name(voice).unwrap(),1u32);
+            self.voice_end=assets.voice_length(voice).map(|length|Instant::now()+length);
+        }
+    }
+
+    /// Озвучка текущей строки ещё не запущена или не закончилась.
+    fn voice_playing(&self)->bool{
+        self.voice.is_some() || self.voice_end.is_some_and(|end|Instant::now()<end)
+    }
+
+    /// Озвучка строки `self.line`: трек `[диалог]/[метка]`,
+    /// если метка стоит прямо перед строкой.
+    fn line_voice(&self)->Option<TrackHandle>{
+        let (anchor,step)=self.dialogue.anchor_before(self.line)?;
+        if step!=self.line{
+            return None
+        }
+
+        let name=format!("{}/{}",self.story.page_table().pages()[self.page].dialogue,anchor);
+        let track=unsafe{assets.track(&name)}.ok()?;
+        unsafe{assets.voice_length(track)}?;
+        Some(track)
+    }
+
+    /// Переход к следующей строке.
+    fn next_line(&mut self,window:&Window,graphics:&mut Graphics2D)->Option<Game>{
+        let next=match self.seek(self.line+1){
+            Some(line)=>{
+                self.line=line;
+                self.show_line(graphics);
+                true
+            }
+            None=>self.next_page(self.page+1,window,graphics),
+        };
+
+        self.moved(next)
+    }
+
+    /// Выбор варианта: присваивания варианта, запись пути
+    /// и переход на страницу варианта или к следующей строке.
+    /// `button` - номер кнопки среди показанных вариантов.
+    fn choose(&mut self,button:usize,window:&Window,graphics:&mut Graphics2D)->Option<Game>{
+        self.remember(graphics);
+        self.remove_choice(graphics);
+
+        let option=self.choice_options[button];
+
+        let target=match self.dialogue.get(self.line){
+            Some(Step::Choice(choice))=>{
+                let option=&choice.options[option];
+                for effect in &option.effects{
+                    effect.apply(unsafe{&mut game_settings.variables});
+                }
+
+                // Выбор записывается как реплика игрока
+                let (user_name,gender)=unsafe{(&game_settings.user_name,game_settings.gender)};
+                self.backlog.push(BacklogLine{
+                    name:user_name.clone(),
+                    text:TextTemplate::new(&option.text).format(user_name,gender),
+                });
+                option.target.clone()
+            }
+            _=>None,
+        };
+
+        unsafe{
+            game_settings.choices.push((self.story.page_table().pages()[self.page].label.clone(),option));
+        }
+
+        match target{
+            Some(label)=>{
+                // Переходы проверены при загрузке диалога
+                let page=self.story.page_table().index_of(&label).unwrap();
+                let next=self.next_page(page,window,graphics);
+                self.moved(next)
+            }
+            None=>self.next_line(window,graphics),
+        }
+    }
+
+    /// Запись текущего состояния для отката.
+    fn remember(&mut self,graphics:&mut Graphics2D){
+        if self.history.len()==rollback_limit{
+            self.history.pop_front();
+        }
+
+        let (variables,choices)=unsafe{(game_settings.variables.clone(),game_settings.choices.len())};
+        let backlog=self.backlog.len();
+
+        self.history.push_back(Snapshot{
+            page:self.page,
+            line:self.line,
+            variables,
+            choices,
+            backlog,
+            name:graphics.get_text_object_text(self.name.index()).clone(),
+            text:self.typewriter.text(),
+            speaker:self.stage.speaker(),
+            shown:self.typewriter.shown(),
+            characters:self.stage.characters().to_vec(),
+        });
+    }
+
+    /// Откат к предыдущей показанной строке: положение, переменные,
+    /// сделанные выборы, персонажи и выведенный текст. После отката выбор можно сделать заново.
+    fn rollback(&mut self,window:&Window,graphics:&mut Graphics2D)->Option<Game>{
+        self.stop_skipping();
+
+        let snapshot=self.history.pop_back()?;
+
+        self.remove_choice(graphics);
+
+        unsafe{
+            game_settings.variables=snapshot.variables;
+            game_settings.choices.truncate(snapshot.choices);
+        }
+
+        if snapshot.page!=self.page{
+            self.load_page(snapshot.page,window,graphics);
+        }
+
+        self.line=snapshot.line;
+        self.show_line(graphics);
+
+        // Строка уже была в истории реплик
+        self.backlog.truncate(snapshot.backlog);
+
+        if self.stage.characters()!=snapshot.characters.as_slice(){
+            self.stage.set_characters(&snapshot.characters,self.resources,self.story.images(),window,graphics);
+        }
+
+        // Вывод реплики продолжается с того же места
+        *graphics.get_text_object_text(self.name.index())=snapshot.name;
+        self.stage.set_speaker(snapshot.speaker,graphics);
+        self.typewriter.set_text_shown(snapshot.text,snapshot.shown,graphics);
+
+        None
+    }
+
+    /// Обработка результата перехода.
+    /// Возвращает результат для выхода со страницы, если история закончилась.
+    fn moved(&mut self,next:bool)->Option<Game>{
+        if next{
+            None
+        }
+        else{
+            self.finish();
+            Some(Game::MainMenu)
+        }
+    }
+
+    /// Конец истории: продолжать больше нечего.
+    fn finish(&mut self){
+        self.finished=true;
+        unsafe{
+            game_settings.continue_game=false;
+        }
+    }
+
+    /// Переход по истории на страницу `page` с автосохранением.
+    fn next_page(&mut self,page:usize,window:&Window,graphics:&mut Graphics2D)->bool{
+        let next=self.go_to(page,0,window,graphics);
+
+        if next{
+            self.autosave(window,graphics);
+        }
+
+        next
+    }
+
+    /// Переход к строке `line` страницы `page`.
+    /// Страницы с ложным условием и без показываемых строк пропускаются.
+    /// Возвращает `false`, если страниц больше нет.
+    fn go_to(&mut self,mut page:usize,mut line:usize,window:&Window,graphics:&mut Graphics2D)->bool{
+        loop{
+            if page>=self.story.page_table().len(){
+                return false
+            }
+
+            if holds(self.story.page_table().pages()[page].condition.as_ref()){
+                self.load_page(page,window,graphics);
+
+                if let Some(line)=self.seek(line){
+                    self.line=line;
+                    break
+                }
+            }
+
+            page+=1;
+            line=0;
+        }
+
+        self.show_line(graphics);
+
+        true
+    }
+
+    /// Переход к месту, сохранённому в `game_settings`.
+    /// Если история изменилась и места больше нет, игра продолжается
+    /// с ближайшего к нему, о чём выводится предупреждение.
+    fn go_to_saved(&mut self,window:&Window,graphics:&mut Graphics2D)->bool{
+        let position=unsafe{game_settings.saved_position.clone()};
+
+        // Откат к строкам до загрузки невозможен
+        self.history.clear();
+
+        let page=position.resolve_page(self.story.page_table());
+        let line=match page{
+            // Страница удалена - с начала ближайшей
+            Resolved::Nearest(_)=>Resolved::Nearest(0),
+            _=>match self.story.page_table().pages().get(page.index()){
+                Some(entry)=>position.resolve_line(self.story.dialogue(&entry.dialogue).unwrap()),
+                None=>Resolved::Exact(0),
+            }
+        };
+
+        #[cfg(debug_assertions)]
+        if page!=Resolved::Exact(page.index()) || line!=Resolved::Exact(line.index()){
+            println!("Сохранённое место {:?} изменилось: страница {:?}, строка {:?}",position,page,line);
+        }
+
+        if let (Resolved::Nearest(_),_)|(_,Resolved::Nearest(_))=(page,line){
+            self.show_notice("История изменилась, игра продолжена с ближайшего места".to_string(),graphics);
+        }
+
+        self.go_to(page.index(),line.index(),window,graphics)
+    }
+
+    /// Поиск показываемой строки, начиная с `line`.
+    /// Присваивания по пути выполняются, строки с ложным условием пропускаются.
+    fn seek(&self,mut line:usize)->Option<usize>{
+        while let Some(step)=self.dialogue.get(line){
+            match step{
+                Step::Line(text_line)=>if holds(text_line.condition.as_ref()){
+                    return Some(line)
+                }
+
+                Step::Choice(choice)=>if choice.options.iter().any(|option|holds(option.condition.as_ref())){
+                    return Some(line)
+                }
+
+                Step::Set{assignment,condition}=>if holds(condition.as_ref()){
+                    assignment.apply(unsafe{&mut game_settings.variables});
+                }
+            }
+
+            line+=1;
+        }
+
+        None
+    }
+
+    /// Загрузка диалога, обоев и персонажей страницы.
+    fn load_page(&mut self,page:usize,window:&Window,graphics:&mut Graphics2D){
+        let wallpaper_texture=unsafe{assets.texture("wallpaper")}.unwrap();
+
+        let story=self.story;
+        let entry=&story.page_table().pages()[page];
+
+        // Диалоги всех страниц загружены при запуске игры
+        self.dialogue=story.dialogue(&entry.dialogue).unwrap();
+
+        if self.wallpaper!=entry.wallpaper{
+            let wallpaper_path=self.story.images().wallpaper(&entry.wallpaper).unwrap();
+            let wallpaper=load_image(&self.resources,wallpaper_path,Some(wallpaper_size()));
+            graphics.get_textured_object_texture(wallpaper_texture.index()).update(&wallpaper);
+            self.wallpaper=entry.wallpaper.clone();
+        }
+
+        self.stage.set_characters(self.dialogue.characters(),&self.resources,self.story.images(),window,graphics);
+
+        self.page=page;
+        self.line=0;
+    }
+
+    /// Вывод имени говорящего и начало вывода текущей реплики
+    /// или вывод вариантов выбора (предыдущая реплика остаётся на экране).
+    fn show_line(&mut self,graphics:&mut Graphics2D){
+        let dialogue_font=unsafe{assets.font("dialogue")}.unwrap().index();
+
+        let (user_name,gender)=unsafe{
+            if let Some(position)=Bookmark::at(self.story.page_table(),self.page,self.dialogue,self.line){
+                game_settings.set_saved_position(position);
+            }
+
+            // Отметка прочитанной строки
+            self.line_read=match game_settings.saved_position.line_key(){
+                Some(key)=>!game_settings.read_lines.insert(key),
+                None=>false,
+            };
+
+            (&game_settings.user_name,game_settings.gender)
+        };
+
+        // Предыдущая озвучка больше не ожидается
+        self.voice=self.line_voice();
+        self.voice_end=None;
+
+        match &self.dialogue.steps()[self.line]{
+            Step::Line(line)=>{
+                let name=match line.speaker{
+                    Speaker::Narrator=>String::new(),
+                    Speaker::Player=>user_name.clone(),
+                    Speaker::Character(_)=>self.dialogue.character(line.speaker).unwrap().name.clone(),
+                };
+
+                let text=TextTemplate::new(&line.text).format(user_name,gender);
+
+                self.backlog.push(BacklogLine{
+                    name:name.clone(),
+                    text:text.clone(),
+                });
+
+                *graphics.get_text_object_text(self.name.index())=name;
+                self.stage.set_speaker(line.speaker,graphics);
+                self.typewriter.set_text(text,graphics);
+            }
+
+            Step::Choice(choice)=>{
+                // Показываются только варианты с истинным условием
+                self.choice_options=(0..choice.options.len())
+                        .filter(|&option|holds(choice.options[option].condition.as_ref()))
+                        .collect();
+
+                let options:Vec<String>=self.choice_options.iter()
+                        .map(|&option|TextTemplate::new(&choice.options[option].text).format(user_name,gender))
+                        .collect();
+
+                let settings=MenuSettings::new("",options.into_iter())
+                        .layout(unsafe{[0f32,0f32,window_width,self.dialogue_box_y]})
+                        .font(dialogue_font)
+                        .button_size(unsafe{[window_width/2f32,choice_button_height]})
+                        .button_font_size(choice_font_size);
+
+                self.choice=Some(Menu::new(settings,graphics));
+            }
+
+            // Присваивания пропускаются при поиске строки
+            Step::Set{..}=>{}
+        }
+    }
+
+    fn draw_shift<S:Surface>(&self,shift:[f32;2],graphics:&mut Graphics<S>){
+        let wallpaper_texture=unsafe{assets.texture("wallpaper")}.unwrap();
+
+        let [dx,dy]=unsafe{mouse_cursor.center_radius()};
+
+        let wallpaper_shift=[
+            dx/wallpaper_movement_scale+shift[0],
+            dy/wallpaper_movement_scale+shift[1]
+        ];
+
+        graphics.draw_shift_textured_object(wallpaper_texture.index(),wallpaper_shift).unwrap();
+        self.stage.draw_shift(shift,graphics);
+        graphics.draw_shift_textured_object(self.dialogue_box,shift).unwrap();
+
+        self.name.draw_shift(shift,graphics);
+        self.text.draw_shift(shift,graphics);
+
+        if let Some(choice)=&self.choice{
+            choice.draw_shift(shift,graphics);
+        }
+    }
+
+    fn render_to_texture(&self,window:&Window,graphics:&mut Graphics2D){
+        let swipe_screen_texture=get_swipe_texture(graphics);
+
+        draw_on_texture(&swipe_screen_texture,window,graphics,|graphics|{
+            self.draw_shift([0f32;2],graphics);
+        });
+    }
+
+    /// Удаление кнопок выбора.
+    fn remove_choice(&mut self,graphics:&mut Graphics2D){
+        if let Some(choice)=self.choice.take(){
+            for _ in 0..choice.len(){
+                graphics.remove_last_simple_object();
+            }
+            // Кнопки и заголовок
+            for _ in 0..choice.len()+1{
+                graphics.remove_last_text_object();
+            }
+        }
+    }
+
+    fn remove_objects(&mut self,graphics:&mut Graphics2D){
+        // Варианты выбора
+        self.remove_choice(graphics);
+        // Персонажи
+        self.stage.clear(graphics);
+        // Значок авточтения, уведомление, имя и реплика
+        for _ in 0..4{
+            graphics.remove_last_text_object();
+        }
+        // Окно диалога
+        graphics.remove_last_textured_object();
+        graphics.remove_last_texture();
+    }
+}
+
+/// Проверка условия по текущим значениям переменных.
+/// Отсутствие условия считается истинным.
+fn holds(condition:Option<&Condition>)->bool{
+    condition.map_or(true,|condition|condition.is_true(unsafe{&game_settings.variables}))
+}
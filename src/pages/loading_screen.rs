@@ -1,21 +1,20 @@
 use crate::{
     // statics
     game_settings,
+    assets,
     // consts
     alphabet,
-    fonts_paths,
-    audio_tracks_paths,
-    main_menu_wallpaper_path,
-    decoration_image_paths,
+    font_names,
+    menu_image_names,
+    story_paths,
     wallpaper_movement_scale,
-    mouse_cursor_icon_index,
-    swipe_screen_index,
     // enums
     Game,
     // structs
     LoadingMainData,
     // fns
     load_image,
+    wallpaper_size,
     draw_on_texture,
     get_swipe_texture,
     make_screenshot,
@@ -23,6 +22,10 @@ use crate::{
 
 use lib::{
     colours::White,
+    story::Story,
+    manifest::{Manifest,ImageRole,TrackCategory},
+    mp3,
+    resources::Resources,
     loading_flag::{
         ThreadState,
         LoadingFlag,
@@ -78,9 +81,10 @@ pub struct LoadingScreen{
 }
 
 impl LoadingScreen{
-    pub fn new(window:&Window,graphics:&mut Graphics2D)->LoadingScreen{
+    pub fn new(resources:&Resources,manifest:&Manifest,window:&Window,graphics:&mut Graphics2D)->LoadingScreen{
         // Создание основы для иконки загрузки
-        let loading_screen_assets=Texture::from_path("./resources/images/loading_screen_assets.png",window.display()).unwrap();
+        let loading_screen_assets=load_image(resources,&manifest.image("loading_screen").unwrap().path,None);
+        let loading_screen_assets=Texture::from_image(&loading_screen_assets,window.display()).unwrap();
         let loading_screen_assets=graphics.add_texture(loading_screen_assets);
 
         // Шестерня
@@ -119,6 +123,21 @@ impl LoadingScreen{
 
         let mut loading_flag=LoadingFlag::new();
         let loading_flag_ptr=loading_flag.ptr();
+        // Ресурсы для загрузки (названия проверены при чтении списка)
+        let fonts_paths:Vec<String>=font_names.iter()
+                .map(|&name|manifest.font(name).unwrap().path.clone())
+                .collect();
+        let tracks:Vec<(String,String,TrackCategory)>=manifest.tracks().iter()
+                .map(|track|(track.name.clone(),track.path.clone(),track.category))
+                .collect();
+        // Обои главного меню и лепесток
+        let images:Vec<(String,String,ImageRole)>=menu_image_names.iter()
+                .map(|&name|manifest.image(name).unwrap())
+                .map(|image|(image.name.clone(),image.path.clone(),image.role))
+                .collect();
+
+        let resources=resources.clone();
+
         let thread=spawn(move||{
             // Для переноса в поток
             let loading_flag=loading_flag_ptr;
@@ -127,45 +146,55 @@ impl LoadingScreen{
 
             let mut fonts=Vec::new();
             // Загрузка шрифтов
-            for path in fonts_paths{
+            for path in &fonts_paths{
                 if let ThreadState::Finished=loading_flag.get_state(){
                     return data
                 }
-                let font=FontOwner::load(path).unwrap();
+                // Шрифты и треки загружаются только из файлов
+                let font=FontOwner::load(resources.local_path(path).unwrap()).unwrap();
                 fonts.push(font);
             }
 
             data.fonts=Some(fonts);
 
             // Загрузка аудио
-            for path in audio_tracks_paths{
+            for (name,path,category) in tracks{
+                if let ThreadState::Finished=loading_flag.get_state(){
+                    return data
+                }
+                let audio=ChanneledTrack::new(resources.local_path(&path).unwrap()).unwrap();
+                // Длительность нужна авточтению, чтобы дождаться конца озвучки
+                let length=match category{
+                    TrackCategory::Voice=>{
+                        let length=resources.read(&path).ok().and_then(|data|mp3::duration(&data));
+                        #[cfg(debug_assertions)]
+                        if length.is_none(){
+                            println!("Не удалось определить длительность озвучки {}",path);
+                        }
+                        length
+                    }
+                    TrackCategory::Music|TrackCategory::Sound=>None,
+                };
+                data.audio.push((name,audio,length));
+            }
+
+            // Загрузка обоев главного меню и украшений
+            for (name,path,role) in images{
                 if let ThreadState::Finished=loading_flag.get_state(){
                     return data
                 }
-                let audio=ChanneledTrack::new(path).unwrap();
-                data.audio.push(audio);
+                let size=match role{
+                    ImageRole::Wallpaper=>Some(wallpaper_size()),
+                    ImageRole::Interface|ImageRole::Decoration=>None,
+                };
+                data.images.push((name,load_image(&resources,&path,size)));
             }
 
             if let ThreadState::Finished=loading_flag.get_state(){
                 return data
             }
-            // Размеры изображения для обоев
-            let (width,height)=unsafe{
-                let dx=window_width/(wallpaper_movement_scale*2f32);
-                let dy=window_height/(wallpaper_movement_scale*2f32);
-                let width=(window_width+2f32*dx).ceil();
-                let height=(window_height+2f32*dy).ceil();
-
-                (width as u32,height as u32)
-            };
-            // Загрузка обоев главного меню
-            let main_menu_wallpaper=load_image(main_menu_wallpaper_path,Some([width,height]));
-            data.textures.push(main_menu_wallpaper);
-
-            for path in decoration_image_paths{
-                let image=load_image(path,None);
-                data.textures.push(image);
-            }
+            // Загрузка и проверка истории
+            data.story=Some(Story::load(&resources,&story_paths));
 
             // Передача данных
             data
@@ -181,6 +210,8 @@ impl LoadingScreen{
     }
 
     pub fn run(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper,data:&mut LoadingMainData)->Game{
+        let cursor=unsafe{assets.texture("cursor")}.unwrap();
+
         let mut frames=0u8;
         let mut angle=0f32;
 
@@ -308,7 +339,7 @@ impl LoadingScreen{
                         // Рендеринг шестерни
                         graphics.draw_rotate_textured_object(self.gear,unsafe{window_center},angle).unwrap();
                         // Рендеринг курсора
-                        graphics.draw_shift_textured_object(mouse_cursor_icon_index,[dx,dy]).unwrap();
+                        graphics.draw_shift_textured_object(cursor.index(),[dx,dy]).unwrap();
                     }).unwrap();
                 }
 
@@ -1,13 +1,11 @@
 use crate::{
     // consts
     game_name,
-    mouse_cursor_icon_index,
-    wallpaper_index,
     wallpaper_movement_scale,
-    swipe_screen_index,
     swipe_updates,
     // statics
     game_settings,
+    assets,
     // enums
     Game,
     // functions
@@ -19,6 +17,8 @@ use crate::{
 use super::{
     // structs
     Settings,
+    SaveSlotsPage,
+    SlotsMode,
     // consts
     button_pressed,
     // enums
@@ -30,9 +30,15 @@ use lib::{
     user_interface::{
         Menu,
         MenuSettings,
+        Button,
+        ButtonSettings,
         EditTextView,
         EditTextViewSettings,
+        TextView,
+        TextViewSettings,
+        GeneralSettings,
     },
+    story::{Gender,Bookmark},
 };
 
 use cat_engine::{
@@ -71,6 +77,15 @@ const y_min_speed:f32=-1f32;
 const x_accelerate:f32=1f32/32f32;
 const y_accelerate:f32=1f32/32f32;
 
+/// Кнопки выбора рода при вводе имени (мужской, женский).
+const gender_buttons:[(&'static str,Gender);2]=[
+    ("Он",Gender::Male),
+    ("Она",Gender::Female),
+];
+
+/// Кнопки подтверждения новой игры поверх сохранённого прогресса.
+const confirm_buttons:[&'static str;2]=["Да","Нет"];
+
 const leaf_spawn_times:&[u16]=&[
     44,153,10,93,
     588,97,126,642,
@@ -116,26 +131,43 @@ pub struct MainMenu{
     leaf:usize,
     leaves:Vec<Leaf>,
     menu:Menu,
+    // Есть ли в меню кнопка "Продолжить"
+    continue_button:bool,
     enter_name:bool,
     user_name:EditTextView,
+    // Кнопки выбора рода игрока
+    gender:Vec<Button>,
+    // Выбранный род, сохраняется только при начале новой игры
+    user_gender:Gender,
+    // Подтверждение новой игры при сохранённом прогрессе
+    confirm_new_game:bool,
+    confirm_text:TextView,
+    confirm:Vec<Button>,
 }
 
 impl MainMenu{
-    pub fn new(window:&Window,graphics:&mut Graphics2D,images:&[RgbaImage])->MainMenu{
+    pub fn new(window:&Window,graphics:&mut Graphics2D)->MainMenu{
+        let wallpaper_texture=unsafe{assets.texture("wallpaper")}.unwrap();
+        let wallpaper=unsafe{assets.image("main_menu_wallpaper")}.unwrap();
+        let leaf=unsafe{assets.image("leaf")}.unwrap();
+
         // Изменение картинки обоев
-        graphics.get_textured_object_texture(wallpaper_index).update(&images[0]);
+        graphics.get_textured_object_texture(wallpaper_texture.index()).update(wallpaper);
 
-        let leaf=Texture::from_image(&images[1],window.display()).unwrap();
+        let leaf=Texture::from_image(leaf,window.display()).unwrap();
         let leaf=graphics.add_texture(leaf);
         let leaf_image_base=ImageBase::new([0f32,-100f32,100f32,100f32],White);
         let leaf=graphics.add_textured_object(&leaf_image_base,leaf).unwrap();
 
 
-        let mut buttons=Vec::with_capacity(4);
-        if unsafe{game_settings.continue_game}{
+        let continue_button=unsafe{game_settings.continue_game};
+
+        let mut buttons=Vec::with_capacity(5);
+        if continue_button{
             buttons.push("Продолжить");
         }
         buttons.push("Новая игра");
+        buttons.push("Загрузить");
         buttons.push("Настройки");
         buttons.push("Выход");
 
@@ -152,16 +184,73 @@ impl MainMenu{
         ]};
         let enter_name_settings=EditTextViewSettings::new("",enter_name_rect);
 
+        // Кнопки рода под полем ввода имени, выбранная выделена
+        let user_gender=unsafe{game_settings.gender};
+        let button_width=(enter_name_rect[2]-10f32)/2f32;
+        let mut gender=Vec::with_capacity(gender_buttons.len());
+        for (c,&(text,button_gender)) in gender_buttons.iter().enumerate(){
+            let rect=[
+                enter_name_rect[0]+c as f32*(button_width+10f32),
+                enter_name_rect[1]+enter_name_rect[3]+10f32,
+                button_width,
+                50f32,
+            ];
+            let colour=if user_gender==button_gender{
+                Light_blue
+            }
+            else{
+                Gray
+            };
+            let settings=ButtonSettings::new(text,rect)
+                    .background_colour(colour)
+                    .font_size(24f32);
+            gender.push(Button::new(settings,graphics));
+        }
+
+        // Вопрос и кнопки "Да" и "Нет" под ним
+        let confirm_rect=unsafe{[
+            window_center[0]-300f32,
+            window_center[1]-100f32,
+            600f32,
+            100f32,
+        ]};
+        let confirm_settings=TextViewSettings::new("Начать заново? Текущий прогресс будет потерян",GeneralSettings::new(confirm_rect))
+                .font_size(26f32)
+                .text_colour(White);
+        let confirm_text=TextView::new(confirm_settings,graphics);
+
+        let mut confirm=Vec::with_capacity(confirm_buttons.len());
+        for (c,&text) in confirm_buttons.iter().enumerate(){
+            let rect=[
+                confirm_rect[0]+150f32+c as f32*160f32,
+                confirm_rect[1]+confirm_rect[3]+10f32,
+                140f32,
+                50f32,
+            ];
+            let settings=ButtonSettings::new(text,rect)
+                    .font_size(24f32);
+            confirm.push(Button::new(settings,graphics));
+        }
+
         Self{
             leaf,
             leaves:Vec::with_capacity(10),
             menu:Menu::new(menu_settings,graphics),
+            continue_button,
             enter_name:false,
             user_name:EditTextView::new(enter_name_settings,graphics),
+            gender,
+            user_gender,
+            confirm_new_game:false,
+            confirm_text,
+            confirm,
         }
     }
 
     pub fn open(&mut self,window:&mut Window,swipe_direction:SwipeDirection,graphics:&mut Graphics2D)->Game{
+        let swipe_screen=unsafe{assets.texture("swipe_screen")}.unwrap();
+        let wallpaper_texture=unsafe{assets.texture("wallpaper")}.unwrap();
+
         let mut result=Game::Next;
 
         let mut frames=0u8;
@@ -252,8 +341,8 @@ impl MainMenu{
                     ];
 
                     window.draw(&graphics,|graphics|{
-                        graphics.draw_shift_textured_object(swipe_screen_index,current_page_shift);
-                        graphics.draw_shift_textured_object(wallpaper_index,wallpaper_shift).unwrap();
+                        graphics.draw_shift_textured_object(swipe_screen.index(),current_page_shift);
+                        graphics.draw_shift_textured_object(wallpaper_texture.index(),wallpaper_shift).unwrap();
 
 
                         for leaf in &self.leaves{
@@ -276,6 +365,10 @@ impl MainMenu{
     }
 
     pub fn run(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Game{
+        let cursor=unsafe{assets.texture("cursor")}.unwrap();
+        let button_sound=unsafe{assets.track("button_pressed")}.unwrap();
+        let wallpaper_texture=unsafe{assets.texture("wallpaper")}.unwrap();
+
         let mut result=Game::Next;
 
         let mut frames=0u16;
@@ -380,7 +473,7 @@ impl MainMenu{
 
                     window.draw(graphics,|graphics|{
                         // Отрисовка обоев
-                        graphics.draw_shift_textured_object(wallpaper_index,wallpaper_shift).unwrap();
+                        graphics.draw_shift_textured_object(wallpaper_texture.index(),wallpaper_shift).unwrap();
 
                         for leaf in &self.leaves{
                             let leaf=[
@@ -395,10 +488,20 @@ impl MainMenu{
 
                         if self.enter_name{
                             self.user_name.draw(graphics);
+                            for button in &self.gender{
+                                button.draw(graphics);
+                            }
+                        }
+
+                        if self.confirm_new_game{
+                            self.confirm_text.draw(graphics);
+                            for button in &self.confirm{
+                                button.draw(graphics);
+                            }
                         }
 
                         // Отрисовка курсора
-                        graphics.draw_shift_textured_object(mouse_cursor_icon_index,[dx,dy]).unwrap();
+                        graphics.draw_shift_textured_object(cursor.index(),[dx,dy]).unwrap();
                     }).unwrap();
                 }
 
@@ -406,8 +509,28 @@ impl MainMenu{
                     if let MouseButton::Left=button{
                         let [mut x,mut y]=unsafe{mouse_cursor.position()};
 
-                        if self.enter_name{
-                            if !self.user_name.in_area(x,y){
+                        if self.confirm_new_game{
+                            match self.confirm.iter().position(|button|button.in_area(x,y)){
+                                // Да
+                                Some(0)=>{
+                                    audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                                    self.start_new_game(graphics);
+                                    window.stop_events();
+                                }
+                                // Нет или нажатие мимо кнопок
+                                Some(_)=>{
+                                    audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                                    self.confirm_new_game=false;
+                                }
+                                None=>self.confirm_new_game=false,
+                            }
+                        }
+                        else if self.enter_name{
+                            if let Some(button)=self.gender.iter().position(|button|button.in_area(x,y)){
+                                audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                                self.set_gender(gender_buttons[button].1,graphics);
+                            }
+                            else if !self.user_name.in_area(x,y){
                                 self.enter_name=false;
                             }
                         }
@@ -421,7 +544,7 @@ impl MainMenu{
                             x-=menu_shift[0];
                             y-=menu_shift[1];
                             if let Some(button)=self.menu.pressed(x,y){
-                                audio.play_track("button_pressed",1u32);
+                                audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
                                 // Получение индекса кнопки
                                 let button_index=self.menu.button_index(button);
                                 // Изменение цвета кнопки
@@ -433,7 +556,7 @@ impl MainMenu{
 
                 WindowEvent::MouseReleased(button)=>{
                     if let MouseButton::Left=button{
-                        if !self.enter_name{
+                        if !self.enter_name && !self.confirm_new_game{
                             if let Some(pressed_button)=self.menu.pressed_button(){
                                 // Текущее положение курсора
                                 let [mut x,mut y]=unsafe{mouse_cursor.position()};
@@ -455,12 +578,12 @@ impl MainMenu{
                                 *graphics.get_simple_object_colour(button_index)=Light_blue;
 
                                 if let Some(mut button)=self.menu.released(x,y){
-                                    if unsafe{!game_settings.continue_game}{
+                                    if !self.continue_button{
                                         button+=1;
                                     }
 
                                     match button{
-                                        // Продолжить игру
+                                        // Продолжить игру с сохранённого места
                                         0=>{
                                             window.stop_events();
                                         }
@@ -471,10 +594,33 @@ impl MainMenu{
                                             self.enter_name=true;
                                         }
 
-                                        // Настройки
+                                        // Загрузка сохранения
                                         2=>{
                                             self.render_to_texture(window,graphics);
 
+                                            match{
+                                                let mut slots=SaveSlotsPage::new(SlotsMode::Load,window,graphics);
+                                                slots.open(window,graphics);
+                                                slots.run(window,graphics,audio)
+                                            }{
+                                                Game::Exit=>{
+                                                    result=Game::Exit;
+                                                    window.stop_events();
+                                                }
+                                                // Продолжение с загруженного места
+                                                Game::Load=>{
+                                                    window.stop_events();
+                                                }
+                                                _=>{
+                                                    self.open(window,SwipeDirection::Right,graphics);
+                                                }
+                                            }
+                                        }
+
+                                        // Настройки
+                                        3=>{
+                                            self.render_to_texture(window,graphics);
+
                                             match{
                                                 let mut settings=Settings::new(window,graphics);
                                                 settings.open(window,graphics);
@@ -491,7 +637,7 @@ impl MainMenu{
                                         }
 
                                         // Выход
-                                        3=>{
+                                        4=>{
                                             window.stop_events();
                                             result=Game::Exit;
                                         }
@@ -509,15 +655,26 @@ impl MainMenu{
                 }
 
                 WindowEvent::KeyboardPressed(button)=>match button{
-                    KeyboardButton::Escape=>self.enter_name=false,
+                    KeyboardButton::Escape=>{
+                        self.enter_name=false;
+                        self.confirm_new_game=false;
+                    }
 
                     KeyboardButton::Backspace=>if self.enter_name{
                         self.user_name.pop_char(graphics);
                     }
 
                     KeyboardButton::Enter=>if self.enter_name{
-                        unsafe{game_settings.user_name=self.user_name.text(graphics).clone()}
-                        window.stop_events();
+                        self.enter_name=false;
+
+                        if unsafe{game_settings.continue_game}{
+                            // Прогресс перезаписывается только после подтверждения
+                            self.confirm_new_game=true;
+                        }
+                        else{
+                            self.start_new_game(graphics);
+                            window.stop_events();
+                        }
                     }
 
                     KeyboardButton::F5=>make_screenshot(window,audio),
@@ -536,10 +693,44 @@ impl MainMenu{
         graphics.remove_all_simple_objects();
         // Удаление всех текстовых объектов
         graphics.remove_all_text_objects();
+        // Удаление листа (добавлен последним после главных объектов)
+        graphics.remove_last_textured_object();
+        graphics.remove_last_texture();
         result
     }
 
+    /// Новая игра с введённым именем и родом - с первой страницы,
+    /// новый путь и начальные значения переменных.
+    fn start_new_game(&self,graphics:&mut Graphics2D){
+        unsafe{
+            game_settings.user_name=self.user_name.text(graphics).clone();
+            game_settings.gender=self.user_gender;
+            game_settings.set_saved_position(Bookmark::new());
+            game_settings.choices.clear();
+            game_settings.variables.clear();
+            game_settings.playtime=0;
+            game_settings.continue_game=true;
+        }
+    }
+
+    /// Выбор рода игрока с выделением его кнопки.
+    fn set_gender(&mut self,gender:Gender,graphics:&mut Graphics2D){
+        self.user_gender=gender;
+
+        for (button,&(_,button_gender)) in self.gender.iter().zip(gender_buttons.iter()){
+            let colour=if button_gender==gender{
+                Light_blue
+            }
+            else{
+                Gray
+            };
+            *graphics.get_simple_object_colour(button.background_index())=colour;
+        }
+    }
+
     fn render_to_texture(&self,window:&Window,graphics:&mut Graphics2D){
+        let wallpaper_texture=unsafe{assets.texture("wallpaper")}.unwrap();
+
         // Расстояние от курсора до центра экрана
         let [dx,dy]=unsafe{mouse_cursor.center_radius()};
 
@@ -561,7 +752,7 @@ impl MainMenu{
         ];
 
         draw_on_texture(&swipe_screen_texture,window,graphics,|graphics|{
-            graphics.draw_shift_textured_object(wallpaper_index,wallpaper_shift).unwrap();
+            graphics.draw_shift_textured_object(wallpaper_texture.index(),wallpaper_shift).unwrap();
 
             for leaf in &self.leaves{
                 let leaf=[
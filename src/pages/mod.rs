@@ -7,6 +7,18 @@ pub use main_menu::MainMenu;
 mod settings;
 pub use settings::Settings;
 
+mod game_page;
+pub use game_page::GamePage;
+
+mod save_slots;
+pub use save_slots::{SaveSlotsPage,SlotsMode};
+
+mod backlog;
+pub use backlog::{BacklogPage,BacklogLine};
+
+mod pause_menu;
+pub use pause_menu::PauseMenu;
+
 use lib::colours::Light_blue;
 
 use cat_engine::Colour;
@@ -0,0 +1,307 @@
+use crate::{
+    // consts
+    swipe_updates,
+    // statics
+    assets,
+    // enums
+    Game,
+    // functions
+    get_swipe_texture,
+    draw_on_texture,
+    make_screenshot,
+};
+
+use super::{
+    button_pressed,
+    Settings,
+    SaveSlotsPage,
+    SlotsMode,
+};
+
+use lib::{
+    colours::{White,Light_blue,Pause_menu_background_colour},
+    user_interface::{
+        Menu,
+        MenuSettings,
+    },
+};
+
+use cat_engine::{
+    // statics
+    mouse_cursor,
+    window_center,
+    window_height,
+    window_width,
+    // enums
+    KeyboardButton,
+    // structs
+    Window,
+    WindowEvent,
+    MouseButton,
+    graphics::{Graphics,Graphics2D},
+    texture::{ImageBase,Texture},
+    shapes::Rectangle,
+    image::RgbaImage,
+    audio::AudioWrapper,
+
+    glium::Surface,
+};
+
+/// Размер панели меню.
+const panel_size:[f32;2]=[400f32,600f32];
+
+const buttons:&[&'static str]=&[
+    "Продолжить",
+    "Сохранить",
+    "Загрузить",
+    "Настройки",
+    "Главное меню",
+    "Выход",
+];
+
+/// Меню паузы поверх остановленной сцены игры.
+pub struct PauseMenu{
+    // Снимок сцены для сохранения
+    screen:RgbaImage,
+    scene:usize,
+    panel:usize,
+    menu:Menu,
+}
+
+impl PauseMenu{
+    /// `screen` - снимок сцены игры размером с окно.
+    pub fn new(screen:RgbaImage,window:&Window,graphics:&mut Graphics2D)->PauseMenu{
+        let scene=Texture::from_image(&screen,window.display()).unwrap();
+        let scene=graphics.add_texture(scene);
+        let scene=graphics.add_textured_object(&ImageBase::new(unsafe{[0f32,0f32,window_width,window_height]},White),scene).unwrap();
+
+        let panel_rect=unsafe{[
+            window_center[0]-panel_size[0]/2f32,
+            window_center[1]-panel_size[1]/2f32,
+            panel_size[0],
+            panel_size[1],
+        ]};
+        let panel=graphics.add_simple_object(&Rectangle::new(panel_rect,Pause_menu_background_colour)).unwrap();
+
+        let menu_settings=MenuSettings::new("Пауза",buttons.iter().copied())
+                .layout(panel_rect)
+                .header_size([panel_size[0],60f32])
+                .button_size([panel_size[0]-100f32,60f32])
+                .button_font_size(26f32);
+        let menu=Menu::new(menu_settings,graphics);
+
+        Self{
+            screen,
+            scene,
+            panel,
+            menu,
+        }
+    }
+
+    /// Возврат со страницы, открытой из меню (картинка `swipe_screen`), слева направо.
+    pub fn open(&mut self,window:&mut Window,graphics:&mut Graphics2D)->Game{
+        let swipe_screen=unsafe{assets.texture("swipe_screen")}.unwrap();
+
+        let mut result=Game::Next;
+
+        let mut frames=0u8;
+
+        let mut shift=0f32;
+
+        let dshift=unsafe{window_width/swipe_updates as f32};
+
+        window.run(|window,event|{
+            match event{
+                WindowEvent::CloseRequested=>result=Game::Exit,
+                WindowEvent::Update=>{
+                    frames+=1;
+                    if frames==swipe_updates{
+                        window.stop_events();
+                    }
+                    else{
+                        shift+=dshift;
+                    }
+                }
+
+                WindowEvent::RedrawRequested=>{
+                    let previous_page_shift=unsafe{shift-window_width};
+
+                    window.draw(&graphics,|graphics|{
+                        graphics.draw_shift_textured_object(swipe_screen.index(),[shift,0f32]);
+
+                        self.draw_shift([previous_page_shift,0f32],graphics);
+                    });
+                }
+
+                _=>{}
+            }
+        });
+
+        result
+    }
+
+    /// Возвращает `Game::Next` для продолжения игры,
+    /// `Game::Load`, если загружено сохранение,
+    /// `Game::MainMenu` или `Game::Exit`.
+    pub fn run(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Game{
+        let cursor=unsafe{assets.texture("cursor")}.unwrap();
+        let button_sound=unsafe{assets.track("button_pressed")}.unwrap();
+
+        let mut result=Game::Next;
+
+        window.run(|window,event|{
+            match event{
+                WindowEvent::CloseRequested=>result=Game::Exit,
+
+                WindowEvent::RedrawRequested=>{
+                    let [dx,dy]=unsafe{mouse_cursor.center_radius()};
+                    window.draw(graphics,|graphics|{
+                        self.draw_shift([0f32;2],graphics);
+
+                        // Отрисовка курсора
+                        graphics.draw_shift_textured_object(cursor.index(),[dx,dy]).unwrap();
+                    }).unwrap();
+                }
+
+                WindowEvent::MousePressed(button)=>{
+                    if let MouseButton::Left=button{
+                        let [x,y]=unsafe{mouse_cursor.position()};
+                        if let Some(button)=self.menu.pressed(x,y){
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                            *graphics.get_simple_object_colour(self.menu.button_index(button))=button_pressed;
+                        }
+                    }
+                }
+
+                WindowEvent::MouseReleased(button)=>{
+                    if let MouseButton::Left=button{
+                        if let Some(pressed_button)=self.menu.pressed_button(){
+                            let [x,y]=unsafe{mouse_cursor.position()};
+                            *graphics.get_simple_object_colour(self.menu.button_index(pressed_button))=Light_blue;
+
+                            if let Some(button)=self.menu.released(x,y){
+                                match button{
+                                    // Продолжить
+                                    0=>window.stop_events(),
+
+                                    // Сохранение и загрузка
+                                    1|2=>{
+                                        self.render_to_texture(window,graphics);
+
+                                        let mode=if button==1{
+                                            SlotsMode::Save{
+                                                screen:self.screen.clone(),
+                                            }
+                                        }
+                                        else{
+                                            SlotsMode::Load
+                                        };
+
+                                        match{
+                                            let mut slots=SaveSlotsPage::new(mode,window,graphics);
+                                            slots.open(window,graphics);
+                                            slots.run(window,graphics,audio)
+                                        }{
+                                            Game::Exit=>{
+                                                result=Game::Exit;
+                                                window.stop_events();
+                                            }
+                                            // Продолжение с загруженного места
+                                            Game::Load=>{
+                                                result=Game::Load;
+                                                window.stop_events();
+                                            }
+                                            _=>if let Game::Exit=self.open(window,graphics){
+                                                result=Game::Exit;
+                                                window.stop_events();
+                                            }
+                                        }
+                                    }
+
+                                    // Настройки
+                                    3=>{
+                                        self.render_to_texture(window,graphics);
+
+                                        match{
+                                            let mut settings=Settings::new(window,graphics);
+                                            settings.open(window,graphics);
+                                            settings.run(window,graphics,audio)
+                                        }{
+                                            Game::Exit=>{
+                                                result=Game::Exit;
+                                                window.stop_events();
+                                            }
+                                            _=>if let Game::Exit=self.open(window,graphics){
+                                                result=Game::Exit;
+                                                window.stop_events();
+                                            }
+                                        }
+                                    }
+
+                                    // Главное меню
+                                    4=>{
+                                        result=Game::MainMenu;
+                                        window.stop_events();
+                                    }
+
+                                    // Выход
+                                    5=>{
+                                        result=Game::Exit;
+                                        window.stop_events();
+                                    }
+
+                                    _=>{}
+                                }
+                            }
+                        }
+                    }
+                }
+
+                WindowEvent::KeyboardPressed(button)=>match button{
+                    KeyboardButton::Escape=>{
+                        window.stop_events();
+                    }
+
+                    KeyboardButton::F5=>make_screenshot(window,audio),
+
+                    _=>{}
+                }
+
+                _=>{}
+            }
+        });
+
+        self.remove_objects(graphics);
+
+        result
+    }
+
+    fn draw_shift<S:Surface>(&self,shift:[f32;2],graphics:&mut Graphics<S>){
+        graphics.draw_shift_textured_object(self.scene,shift).unwrap();
+        graphics.draw_shift_simple_object(self.panel,shift).unwrap();
+        self.menu.draw_shift(shift,graphics);
+    }
+
+    fn render_to_texture(&self,window:&Window,graphics:&mut Graphics2D){
+        let swipe_screen_texture=get_swipe_texture(graphics);
+
+        draw_on_texture(&swipe_screen_texture,window,graphics,|graphics|{
+            self.draw_shift([0f32;2],graphics);
+        });
+    }
+
+    fn remove_objects(&mut self,graphics:&mut Graphics2D){
+        // Кнопки и заголовок меню
+        for _ in 0..self.menu.len(){
+            graphics.remove_last_simple_object();
+        }
+        for _ in 0..self.menu.len()+1{
+            graphics.remove_last_text_object();
+        }
+        // Панель
+        graphics.remove_last_simple_object();
+        // Снимок сцены
+        graphics.remove_last_textured_object();
+        graphics.remove_last_texture();
+    }
+}
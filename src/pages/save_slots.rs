@@ -0,0 +1,430 @@
+use crate::{
+    // consts
+    swipe_updates,
+    // statics
+    assets,
+    // enums
+    Game,
+    // structs
+    saves::{SaveSlot,save_slots,autosave_slots,thumbnail_size},
+    // functions
+    get_swipe_texture,
+    draw_on_texture,
+    make_screenshot,
+};
+
+use super::{
+    button_pressed,
+    settings::page_colour,
+};
+
+use lib::{
+    AlignX,
+    AlignY,
+    colours::{White,Gray,Light_blue},
+    user_interface::{
+        Button,
+        ButtonSettings,
+        TextView,
+        TextViewSettings,
+        GeneralSettings,
+    },
+};
+
+use cat_engine::{
+    // statics
+    mouse_cursor,
+    window_height,
+    window_width,
+    // enums
+    KeyboardButton,
+    // structs
+    Window,
+    WindowEvent,
+    MouseButton,
+    graphics::{Graphics,Graphics2D},
+    texture::{ImageBase,Texture},
+    image::{RgbaImage,Rgba},
+    audio::AudioWrapper,
+
+    glium::Surface,
+};
+
+/// Количество слотов в строке.
+const slots_per_row:usize=3;
+
+/// Отступ сверху до первого ряда.
+const slots_top:f32=120f32;
+
+/// Высота подписи под миниатюрой.
+const caption_height:f32=70f32;
+
+/// Отступ снизу, оставляемый под кнопку "Назад".
+const slots_bottom:f32=80f32;
+
+/// Цвет миниатюры пустого слота.
+const empty_slot_colour:[u8;4]=[40,40,40,255];
+
+pub enum SlotsMode{
+    /// Сохранение текущего положения, `screen` - снимок экрана игры
+    Save{
+        screen:RgbaImage,
+    },
+    /// Загрузка из слотов сохранения и автосохранения
+    Load,
+}
+
+/// Страница слотов сохранения: миниатюры экрана, страница, дата и время игры.
+pub struct SaveSlotsPage{
+    mode:SlotsMode,
+    header:TextView,
+    escape:Button,
+    // Пути файлов и названия слотов
+    paths:Vec<String>,
+    names:Vec<String>,
+    // Прямоугольники миниатюр [x,y,width,height]
+    rects:Vec<[f32;4]>,
+    thumbnails:Vec<usize>,
+    titles:Vec<TextView>,
+    captions:Vec<TextView>,
+    // Загруженные слоты (`None` - пуст)
+    slots:Vec<Option<SaveSlot>>,
+    // Нажатый слот или количество слотов для кнопки "Назад"
+    pressed:Option<usize>,
+}
+
+impl SaveSlotsPage{
+    pub fn new(mode:SlotsMode,window:&Window,graphics:&mut Graphics2D)->SaveSlotsPage{
+        let header_text=match mode{
+            SlotsMode::Save{..}=>"Сохранение",
+            SlotsMode::Load=>"Загрузка",
+        };
+        let header_settings=TextViewSettings::new(header_text,GeneralSettings::new(unsafe{[0f32,0f32,window_width,slots_top]}))
+                .font_size(40f32)
+                .text_colour(White);
+        let header=TextView::new(header_settings,graphics);
+
+        let escape_rect=unsafe{[
+            10f32,
+            window_height-70f32,
+            160f32,
+            60f32,
+        ]};
+        let escape=Button::new(ButtonSettings::new("Назад",escape_rect),graphics);
+
+        // Автосохранения только загружаются
+        let mut paths:Vec<String>=(0..save_slots).map(SaveSlot::path).collect();
+        let mut names:Vec<String>=(1..=save_slots).map(|slot|slot.to_string()).collect();
+        if let SlotsMode::Load=mode{
+            paths.extend((0..autosave_slots).map(SaveSlot::autosave_path));
+            names.extend((1..=autosave_slots).map(|slot|format!("Авто {}",slot)));
+        }
+        let len=paths.len();
+
+        // Три миниатюры в ряд с равными промежутками,
+        // уменьшаются, если ряды не помещаются по высоте
+        let rows=((len+slots_per_row-1)/slots_per_row) as f32;
+        let gap=unsafe{window_width/16f32};
+        let max_row_height=unsafe{(window_height-slots_top-slots_bottom)/rows};
+        let ratio=thumbnail_size[1] as f32/thumbnail_size[0] as f32;
+        let width=unsafe{window_width/4f32}.min((max_row_height-caption_height-gap/2f32)/ratio);
+        let height=width*ratio;
+        let left=unsafe{(window_width-slots_per_row as f32*(width+gap)+gap)/2f32};
+
+        let mut page=Self{
+            mode,
+            header,
+            escape,
+            paths,
+            names,
+            rects:Vec::with_capacity(len),
+            thumbnails:Vec::with_capacity(len),
+            titles:Vec::with_capacity(len),
+            captions:Vec::with_capacity(len),
+            slots:Vec::with_capacity(len),
+            pressed:None,
+        };
+
+        for slot in 0..len{
+            let column=(slot%slots_per_row) as f32;
+            let row=(slot/slots_per_row) as f32;
+            let rect=[
+                left+column*(width+gap),
+                slots_top+row*(height+caption_height+gap/2f32),
+                width,
+                height,
+            ];
+
+            let save=match SaveSlot::load_file(&page.paths[slot]){
+                Ok(save)=>save,
+                Err(e)=>{
+                    #[cfg(debug_assertions)]
+                    println!("{}: {}",page.paths[slot],e);
+                    None
+                }
+            };
+
+            let empty;
+            let thumbnail=match &save{
+                Some(save)=>&save.thumbnail,
+                None=>{
+                    empty=empty_thumbnail();
+                    &empty
+                }
+            };
+            let texture=Texture::from_image(thumbnail,window.display()).unwrap();
+            let texture=graphics.add_texture(texture);
+            let object=graphics.add_textured_object(&ImageBase::new(rect,White),texture).unwrap();
+
+            let (title,caption)=slot_text(&page.names[slot],save.as_ref());
+
+            let title_rect=[rect[0],rect[1]+height,width,caption_height/2f32];
+            let title_settings=TextViewSettings::new(title,GeneralSettings::new(title_rect))
+                    .font_size(22f32)
+                    .text_colour(White)
+                    .align_x(AlignX::Left)
+                    .align_y(AlignY::Center);
+
+            let caption_rect=[rect[0],rect[1]+height+caption_height/2f32,width,caption_height/2f32];
+            let caption_settings=TextViewSettings::new(caption,GeneralSettings::new(caption_rect))
+                    .font_size(18f32)
+                    .text_colour(Gray)
+                    .align_x(AlignX::Left)
+                    .align_y(AlignY::Center);
+
+            page.rects.push(rect);
+            page.thumbnails.push(object);
+            page.titles.push(TextView::new(title_settings,graphics));
+            page.captions.push(TextView::new(caption_settings,graphics));
+            page.slots.push(save);
+        }
+
+        page
+    }
+
+    /// Переход с предыдущей страницы (картинка `swipe_screen`) справа налево.
+    pub fn open(&mut self,window:&mut Window,graphics:&mut Graphics2D)->Game{
+        let swipe_screen=unsafe{assets.texture("swipe_screen")}.unwrap();
+
+        let mut result=Game::Next;
+
+        let mut frames=0u8;
+
+        let mut shift=0f32;
+
+        let dshift=unsafe{window_width/swipe_updates as f32};
+
+        window.run(|window,event|{
+            match event{
+                WindowEvent::CloseRequested=>result=Game::Exit,
+                WindowEvent::Update=>{
+                    frames+=1;
+                    if frames==swipe_updates{
+                        window.stop_events();
+                    }
+                    else{
+                        shift-=dshift;
+                    }
+                }
+
+                WindowEvent::RedrawRequested=>{
+                    let next_page_shift=unsafe{window_width+shift};
+
+                    window.draw(&graphics,|graphics|{
+                        graphics.clear_colour(page_colour);
+
+                        graphics.draw_shift_textured_object(swipe_screen.index(),[shift,0f32]);
+
+                        self.draw_shift([next_page_shift,0f32],graphics);
+                    });
+                }
+
+                _=>{}
+            }
+        });
+
+        result
+    }
+
+    /// Возвращает `Game::Load`, если сохранение загружено,
+    /// `Game::Next` при возврате назад.
+    pub fn run(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Game{
+        let cursor=unsafe{assets.texture("cursor")}.unwrap();
+        let button_sound=unsafe{assets.track("button_pressed")}.unwrap();
+
+        let mut result=Game::Next;
+
+        window.run(|window,event|{
+            match event{
+                WindowEvent::CloseRequested=>result=Game::Exit,
+
+                WindowEvent::RedrawRequested=>{
+                    let [dx,dy]=unsafe{mouse_cursor.center_radius()};
+                    window.draw(graphics,|graphics|{
+                        // Фон
+                        graphics.clear_colour(page_colour);
+
+                        self.draw_shift([0f32;2],graphics);
+
+                        // Отрисовка курсора
+                        graphics.draw_shift_textured_object(cursor.index(),[dx,dy]).unwrap();
+                    }).unwrap();
+                }
+
+                WindowEvent::MousePressed(button)=>{
+                    if let MouseButton::Left=button{
+                        let [x,y]=unsafe{mouse_cursor.position()};
+
+                        self.pressed=None;
+
+                        if self.escape.pressed(x,y){
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                            *graphics.get_simple_object_colour(self.escape.background_index())=button_pressed;
+                            self.pressed=Some(self.paths.len());
+                        }
+                        else if let Some(slot)=self.slot_at(x,y){
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                            self.pressed=Some(slot);
+                        }
+                    }
+                }
+
+                WindowEvent::MouseReleased(button)=>{
+                    if let MouseButton::Left=button{
+                        let [x,y]=unsafe{mouse_cursor.position()};
+
+                        match self.pressed.take(){
+                            Some(escape) if escape==self.paths.len()=>{
+                                *graphics.get_simple_object_colour(self.escape.background_index())=Light_blue;
+                                if self.escape.released(x,y){
+                                    window.stop_events();
+                                }
+                            }
+
+                            Some(slot) if self.slot_at(x,y)==Some(slot)=>{
+                                if self.select(slot,graphics){
+                                    result=Game::Load;
+                                    window.stop_events();
+                                }
+                            }
+
+                            _=>{}
+                        }
+                    }
+                }
+
+                WindowEvent::KeyboardPressed(button)=>match button{
+                    KeyboardButton::Escape=>{
+                        window.stop_events();
+                    }
+
+                    KeyboardButton::F5=>make_screenshot(window,audio),
+
+                    _=>{}
+                }
+
+                _=>{}
+            }
+        });
+
+        self.render_to_texture(window,graphics);
+
+        self.remove_objects(graphics);
+
+        result
+    }
+
+    /// Сохранение в слот или загрузка из него.
+    /// Возвращает `true`, если сохранение загружено.
+    fn select(&mut self,slot:usize,graphics:&mut Graphics2D)->bool{
+        match &self.mode{
+            SlotsMode::Save{screen}=>{
+                let save=SaveSlot::capture(screen);
+
+                if let Err(e)=save.save_file(&self.paths[slot]){
+                    #[cfg(debug_assertions)]
+                    println!("{}: {}",self.paths[slot],e);
+                    return false
+                }
+
+                graphics.get_textured_object_texture(self.thumbnails[slot]).update(&save.thumbnail);
+
+                let (title,caption)=slot_text(&self.names[slot],Some(&save));
+                *graphics.get_text_object_text(self.titles[slot].index())=title;
+                *graphics.get_text_object_text(self.captions[slot].index())=caption;
+
+                self.slots[slot]=Some(save);
+
+                false
+            }
+
+            SlotsMode::Load=>{
+                match &self.slots[slot]{
+                    Some(save)=>{
+                        save.restore();
+                        true
+                    }
+                    None=>false,
+                }
+            }
+        }
+    }
+
+    fn slot_at(&self,x:f32,y:f32)->Option<usize>{
+        self.rects.iter().position(|&[x1,y1,width,height]|{
+            x>x1 && x<x1+width && y>y1 && y<y1+height
+        })
+    }
+
+    fn draw_shift<S:Surface>(&self,shift:[f32;2],graphics:&mut Graphics<S>){
+        self.header.draw_shift(shift,graphics);
+
+        for slot in 0..self.thumbnails.len(){
+            graphics.draw_shift_textured_object(self.thumbnails[slot],shift).unwrap();
+            self.titles[slot].draw_shift(shift,graphics);
+            self.captions[slot].draw_shift(shift,graphics);
+        }
+
+        self.escape.draw_shift(shift,graphics);
+    }
+
+    fn render_to_texture(&self,window:&Window,graphics:&mut Graphics2D){
+        let swipe_screen_texture=get_swipe_texture(graphics);
+
+        draw_on_texture(&swipe_screen_texture,window,graphics,|graphics|{
+            graphics.clear_colour(page_colour);
+
+            self.draw_shift([0f32;2],graphics);
+        });
+    }
+
+    fn remove_objects(&mut self,graphics:&mut Graphics2D){
+        // Миниатюры
+        for _ in 0..self.thumbnails.len(){
+            graphics.remove_last_textured_object();
+            graphics.remove_last_texture();
+        }
+        // Подписи слотов, заголовок и кнопка "Назад"
+        for _ in 0..2*self.thumbnails.len()+2{
+            graphics.remove_last_text_object();
+        }
+        graphics.remove_last_simple_object();
+    }
+}
+
+/// Миниатюра пустого слота.
+fn empty_thumbnail()->RgbaImage{
+    let [width,height]=thumbnail_size;
+    RgbaImage::from_pixel(width,height,Rgba(empty_slot_colour))
+}
+
+/// Название и подпись слота.
+fn slot_text(name:&str,save:Option<&SaveSlot>)->(String,String){
+    match save{
+        Some(save)=>(
+            format!("{}. {} - {}",name,save.user_name,save.position.page_label),
+            format!("{}   {}",save.date(),save.playtime()),
+        ),
+        None=>(format!("{}. Пусто",name),String::new()),
+    }
+}
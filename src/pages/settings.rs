@@ -1,13 +1,11 @@
 use crate::{
     // consts
     game_name,
-    mouse_cursor_icon_index,
-    wallpaper_index,
     wallpaper_movement_scale,
     swipe_updates,
-    swipe_screen_index,
     // statics
     game_settings,
+    assets,
     // enums
     Game,
     // functions
@@ -48,9 +46,18 @@ use cat_engine::{
 
 pub const page_colour:Colour=Dark_gray;
 
+/// Скорости авточтения (знаков в секунду) и их названия.
+const auto_speeds:&[(f32,&str)]=&[
+    (10f32,"медленно"),
+    (15f32,"средне"),
+    (25f32,"быстро"),
+];
+
 pub struct Settings{
     button_pressed:Option<usize>,
     reset_game_progress:Button,
+    skip_mode:Button,
+    auto_speed:Button,
     escape:Button,
 }
 
@@ -74,14 +81,36 @@ impl Settings{
         let reset_game_progress_settings=ButtonSettings::new("Сбросить прогресс игры",reset_game_progress_rect);
         let reset_game_progress=Button::new(reset_game_progress_settings,graphics);
 
+        let skip_mode_rect=unsafe{[
+            window_center[0]-175f32,
+            150f32,
+            350f32,
+            60f32,
+        ]};
+        let skip_mode_settings=ButtonSettings::new(skip_mode_text(),skip_mode_rect);
+        let skip_mode=Button::new(skip_mode_settings,graphics);
+
+        let auto_speed_rect=[
+            skip_mode_rect[0],
+            skip_mode_rect[1]+80f32,
+            skip_mode_rect[2],
+            skip_mode_rect[3],
+        ];
+        let auto_speed_settings=ButtonSettings::new(auto_speed_text(),auto_speed_rect);
+        let auto_speed=Button::new(auto_speed_settings,graphics);
+
         Self{
             button_pressed:None,
             reset_game_progress,
+            skip_mode,
+            auto_speed,
             escape,
         }
     }
 
     pub fn open(&mut self,window:&mut Window,graphics:&mut Graphics2D)->Game{
+        let swipe_screen=unsafe{assets.texture("swipe_screen")}.unwrap();
+
         let mut result=Game::Next;
 
         let mut frames=0u8;
@@ -109,9 +138,11 @@ impl Settings{
                     window.draw(&graphics,|graphics|{
                         graphics.clear_colour(page_colour);
 
-                        graphics.draw_shift_textured_object(swipe_screen_index,[shift,0f32]);
+                        graphics.draw_shift_textured_object(swipe_screen.index(),[shift,0f32]);
 
                         self.reset_game_progress.draw_shift([next_page_shift,0f32],graphics);
+                        self.skip_mode.draw_shift([next_page_shift,0f32],graphics);
+                        self.auto_speed.draw_shift([next_page_shift,0f32],graphics);
                         self.escape.draw_shift([next_page_shift,0f32],graphics);
                     });
                 }
@@ -124,6 +155,9 @@ impl Settings{
     }
 
     pub fn run(&mut self,window:&mut Window,graphics:&mut Graphics2D,audio:&AudioWrapper)->Game{
+        let cursor=unsafe{assets.texture("cursor")}.unwrap();
+        let button_sound=unsafe{assets.track("button_pressed")}.unwrap();
+
         let mut result=Game::Next;
 
         window.run(|window,event|{
@@ -137,10 +171,12 @@ impl Settings{
                         graphics.clear_colour(page_colour);
 
                         self.reset_game_progress.draw(graphics);
+                        self.skip_mode.draw(graphics);
+                        self.auto_speed.draw(graphics);
                         self.escape.draw(graphics);
 
                         // Отрисовка курсора
-                        graphics.draw_shift_textured_object(mouse_cursor_icon_index,[dx,dy]).unwrap();
+                        graphics.draw_shift_textured_object(cursor.index(),[dx,dy]).unwrap();
                     }).unwrap();
                 }
 
@@ -151,12 +187,22 @@ impl Settings{
                         self.button_pressed=None;
 
                         if self.reset_game_progress.pressed(x,y){
-                            audio.play_track("button_pressed",1u32);
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
                             *graphics.get_simple_object_colour(self.reset_game_progress.background_index())=button_pressed;
                             self.button_pressed=Some(self.reset_game_progress.background_index());
                         }
+                        else if self.skip_mode.pressed(x,y){
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                            *graphics.get_simple_object_colour(self.skip_mode.background_index())=button_pressed;
+                            self.button_pressed=Some(self.skip_mode.background_index());
+                        }
+                        else if self.auto_speed.pressed(x,y){
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
+                            *graphics.get_simple_object_colour(self.auto_speed.background_index())=button_pressed;
+                            self.button_pressed=Some(self.auto_speed.background_index());
+                        }
                         else if self.escape.pressed(x,y){
-                            audio.play_track("button_pressed",1u32);
+                            audio.play_track(unsafe{assets.track_name(button_sound)}.unwrap(),1u32);
                             *graphics.get_simple_object_colour(self.escape.background_index())=button_pressed;
                             self.button_pressed=Some(self.escape.background_index());
                         }
@@ -175,6 +221,28 @@ impl Settings{
                                     window.stop_events();
                                 }
                             }
+                            else if button==self.auto_speed.background_index(){
+                                if self.auto_speed.released(x,y){
+                                    // auto speed action
+                                    unsafe{
+                                        // Следующая скорость по кругу
+                                        let current=auto_speeds.iter()
+                                                .position(|&(speed,_)|speed==game_settings.auto_speed)
+                                                .unwrap_or(0);
+                                        game_settings.auto_speed=auto_speeds[(current+1)%auto_speeds.len()].0;
+                                    }
+                                    self.auto_speed.set_text(auto_speed_text(),graphics);
+                                }
+                            }
+                            else if button==self.skip_mode.background_index(){
+                                if self.skip_mode.released(x,y){
+                                    // skip mode action
+                                    unsafe{
+                                        game_settings.skip_all=!game_settings.skip_all;
+                                    }
+                                    self.skip_mode.set_text(skip_mode_text(),graphics);
+                                }
+                            }
                             else{
                                 if self.reset_game_progress.released(x,y){
                                     // reset action
@@ -206,11 +274,13 @@ impl Settings{
         self.render_to_texture(window,graphics);
 
         // Удаление всех простых объектов
-        graphics.remove_last_simple_object();
-        graphics.remove_last_simple_object();
+        for _ in 0..4{
+            graphics.remove_last_simple_object();
+        }
         // Удаление всех текстовых объектов
-        graphics.remove_last_text_object();
-        graphics.remove_last_text_object();
+        for _ in 0..4{
+            graphics.remove_last_text_object();
+        }
         result
     }
 
@@ -221,7 +291,28 @@ impl Settings{
             graphics.clear_colour(page_colour);
 
             self.reset_game_progress.draw(graphics);
+            self.skip_mode.draw(graphics);
+            self.auto_speed.draw(graphics);
             self.escape.draw(graphics);
         });
     }
+}
+
+/// Надпись кнопки режима пропуска (`Tab` на странице игры).
+fn skip_mode_text()->&'static str{
+    if unsafe{game_settings.skip_all}{
+        "Пропуск: все строки"
+    }
+    else{
+        "Пропуск: только прочитанные"
+    }
+}
+
+/// Надпись кнопки скорости авточтения (`F7` на странице игры).
+fn auto_speed_text()->String{
+    let speed=unsafe{game_settings.auto_speed};
+    match auto_speeds.iter().find(|&&(preset,_)|preset==speed){
+        Some((_,name))=>format!("Авточтение: {}",name),
+        None=>format!("Авточтение: {} знаков/с",speed),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,319 @@
+//! Общие части двоичных форматов настроек и сохранений:
+//! заголовок, версия, контрольная сумма и поля истории.
+
+use lib::{
+    story::{Variables,Value,Bookmark},
+    binary::{BinaryWriter,BinaryReader,BinaryError,crc32},
+    atomic_file::backup_path,
+};
+
+use std::{
+    fmt,
+    io,
+    fs::read,
+    path::Path,
+    collections::BTreeSet,
+};
+
+/// Начало записи: заголовок и версия формата.
+pub fn begin(magic:&[u8;4],version:u16)->BinaryWriter{
+    let mut writer=BinaryWriter::new();
+    writer.write_raw(magic);
+    writer.write_u16(version);
+    writer
+}
+
+/// Завершение записи: контрольная сумма (`u32`) всех предыдущих байтов.
+pub fn finish(mut writer:BinaryWriter)->Vec<u8>{
+    let checksum=crc32(writer.as_bytes());
+    writer.write_u32(checksum);
+    writer.into_bytes()
+}
+
+/// Проверка контрольной суммы в конце `data`.
+/// Возвращает данные без неё.
+pub fn verify(data:&[u8])->Result<&[u8],SaveError>{
+    if data.len()<4{
+        return Err(SaveError::Binary(BinaryError::UnexpectedEnd))
+    }
+
+    let (content,checksum)=data.split_at(data.len()-4);
+    if crc32(content)==BinaryReader::new(checksum).read_u32()?{
+        Ok(content)
+    }
+    else{
+        Err(SaveError::ChecksumMismatch)
+    }
+}
+
+/// Чтение файла функцией `parse`.
+/// Если файл повреждён или отсутствует, читается резервная копия (см. `write_atomic`).
+/// `Ok(None)` - нет ни файла, ни копии.
+pub fn load_with_backup<T,F:Fn(&[u8])->Result<T,SaveError>>(path:&str,parse:F)->Result<Option<T>,SaveError>{
+    let read_file=|path:&Path|match read(path){
+        Ok(data)=>parse(&data).map(Some),
+        Err(e) if e.kind()==io::ErrorKind::NotFound=>Ok(None),
+        Err(e)=>Err(SaveError::Io(e)),
+    };
+
+    let error=match read_file(Path::new(path)){
+        Ok(Some(value))=>return Ok(Some(value)),
+        Ok(None)=>None,
+        Err(e)=>Some(e),
+    };
+
+    match read_file(&backup_path(path)){
+        Ok(Some(value))=>{
+            #[cfg(debug_assertions)]
+            if let Some(e)=&error{
+                println!("{}: {}, используется резервная копия",path,e);
+            }
+            Ok(Some(value))
+        }
+        // Ошибка основного файла важнее ошибки копии
+        Ok(None)|Err(_)=>match error{
+            Some(e)=>Err(e),
+            None=>Ok(None),
+        },
+    }
+}
+
+/// Сделанные выборы: количество (`u32`), название страницы и номер варианта (`u32`).
+pub fn write_choices(writer:&mut BinaryWriter,choices:&[(String,usize)]){
+    writer.write_u32(choices.len() as u32);
+    for (label,option) in choices{
+        writer.write_str(label);
+        writer.write_u32(*option as u32);
+    }
+}
+
+pub fn read_choices(reader:&mut BinaryReader)->Result<Vec<(String,usize)>,SaveError>{
+    let len=reader.read_u32()?;
+    let mut choices=Vec::new();
+    for _ in 0..len{
+        let label=reader.read_string()?;
+        let option=reader.read_u32()? as usize;
+        choices.push((label,option));
+    }
+    Ok(choices)
+}
+
+/// Место в истории: название и номер страницы, номер шага (`u64`),
+/// метка (флаг, название и смещение) и хеш шага (флаг и `u32`).
+pub fn write_bookmark(writer:&mut BinaryWriter,bookmark:&Bookmark){
+    writer.write_str(&bookmark.page_label);
+    writer.write_u64(bookmark.page as u64);
+    writer.write_u64(bookmark.line as u64);
+
+    writer.write_bool(bookmark.anchor.is_some());
+    if let Some((anchor,offset))=&bookmark.anchor{
+        writer.write_str(anchor);
+        writer.write_u64(*offset as u64);
+    }
+
+    writer.write_bool(bookmark.hash.is_some());
+    if let Some(hash)=bookmark.hash{
+        writer.write_u32(hash);
+    }
+}
+
+pub fn read_bookmark(reader:&mut BinaryReader)->Result<Bookmark,SaveError>{
+    let page_label=reader.read_string()?;
+    let page=reader.read_u64()? as usize;
+    let line=reader.read_u64()? as usize;
+
+    let anchor=if reader.read_bool()?{
+        Some((reader.read_string()?,reader.read_u64()? as usize))
+    }
+    else{
+        None
+    };
+
+    let hash=if reader.read_bool()?{
+        Some(reader.read_u32()?)
+    }
+    else{
+        None
+    };
+
+    Ok(Bookmark{
+        page_label,
+        page,
+        line,
+        anchor,
+        hash,
+    })
+}
+
+/// Прочитанные строки: количество (`u32`) и ключи (`u64`, см. `Bookmark::line_key`).
+pub fn write_read_lines(writer:&mut BinaryWriter,read_lines:&BTreeSet<u64>){
+    writer.write_u32(read_lines.len() as u32);
+    for &key in read_lines{
+        writer.write_u64(key);
+    }
+}
+
+pub fn read_read_lines(reader:&mut BinaryReader)->Result<BTreeSet<u64>,SaveError>{
+    let len=reader.read_u32()?;
+    let mut read_lines=BTreeSet::new();
+    for _ in 0..len{
+        read_lines.insert(reader.read_u64()?);
+    }
+    Ok(read_lines)
+}
+
+/// Переменные: количество (`u32`), имя, тип (`u8`) и значение.
+pub fn write_variables(writer:&mut BinaryWriter,variables:&Variables){
+    writer.write_u32(variables.iter().count() as u32);
+    for (name,value) in variables.iter(){
+        writer.write_str(name);
+        match *value{
+            Value::Integer(value)=>{
+                writer.write_u8(0);
+                writer.write_i64(value);
+            }
+            Value::Boolean(value)=>{
+                writer.write_u8(1);
+                writer.write_bool(value);
+            }
+        }
+    }
+}
+
+pub fn read_variables(reader:&mut BinaryReader)->Result<Variables,SaveError>{
+    let len=reader.read_u32()?;
+    let mut variables=Variables::new();
+    for _ in 0..len{
+        let name=reader.read_string()?;
+        let value=match reader.read_u8()?{
+            0=>Value::Integer(reader.read_i64()?),
+            1=>Value::Boolean(reader.read_bool()?),
+            _=>return Err(SaveError::InvalidValue("variable type")),
+        };
+        variables.set(&name,value);
+    }
+    Ok(variables)
+}
+
+#[derive(Debug)]
+pub enum SaveError{
+    Io(io::Error),
+    Binary(BinaryError),
+    /// Версия формата новее поддерживаемой.
+    UnsupportedVersion(u16),
+    /// Файл повреждён.
+    ChecksumMismatch,
+    /// Недопустимое значение поля.
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for SaveError{
+    fn fmt(&self,f:&mut fmt::Formatter)->fmt::Result{
+        match self{
+            SaveError::Io(e)=>e.fmt(f),
+            SaveError::Binary(e)=>e.fmt(f),
+            SaveError::UnsupportedVersion(version)=>write!(f,"неподдерживаемая версия формата {}",version),
+            SaveError::ChecksumMismatch=>write!(f,"неверная контрольная сумма"),
+            SaveError::InvalidValue(field)=>write!(f,"недопустимое значение поля `{}`",field),
+        }
+    }
+}
+
+impl std::error::Error for SaveError{}
+
+impl From<io::Error> for SaveError{
+    fn from(e:io::Error)->SaveError{
+        SaveError::Io(e)
+    }
+}
+
+impl From<BinaryError> for SaveError{
+    fn from(e:BinaryError)->SaveError{
+        SaveError::Binary(e)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const magic:&[u8;4]=b"TEST";
+
+    fn bookmark()->Bookmark{
+        Bookmark{
+            page_label:"bridge".to_string(),
+            page:3,
+            line:12,
+            anchor:Some(("выбор".to_string(),2)),
+            hash:Some(0xCAFE),
+        }
+    }
+
+    fn variables()->Variables{
+        let mut variables=Variables::new();
+        variables.set("coins",Value::Integer(-5));
+        variables.set("brave",Value::Boolean(true));
+        variables
+    }
+
+    /// Запись всех полей истории с заголовком и контрольной суммой.
+    fn write_fields()->Vec<u8>{
+        let mut writer=begin(magic,1);
+        write_bookmark(&mut writer,&bookmark());
+        write_bookmark(&mut writer,&Bookmark::new());
+        write_choices(&mut writer,&[("start".to_string(),1),("bridge".to_string(),0)]);
+        write_variables(&mut writer,&variables());
+        write_read_lines(&mut writer,&[1u64,u64::MAX].iter().copied().collect());
+        finish(writer)
+    }
+
+    #[test]
+    fn fields_round_trip(){
+        let data=write_fields();
+        let content=verify(&data).unwrap();
+        assert_eq!(content.len(),data.len()-4);
+
+        let mut reader=BinaryReader::new(content);
+        assert_eq!(reader.read_raw(4).unwrap(),magic);
+        assert_eq!(reader.read_u16().unwrap(),1);
+
+        assert_eq!(read_bookmark(&mut reader).unwrap(),bookmark());
+        assert_eq!(read_bookmark(&mut reader).unwrap(),Bookmark::new());
+        assert_eq!(read_choices(&mut reader).unwrap(),vec![("start".to_string(),1),("bridge".to_string(),0)]);
+        assert_eq!(read_variables(&mut reader).unwrap(),variables());
+        assert_eq!(read_read_lines(&mut reader).unwrap().into_iter().collect::<Vec<u64>>(),vec![1,u64::MAX]);
+        assert!(reader.is_end());
+    }
+
+    #[test]
+    fn checksum_mismatch(){
+        let mut data=write_fields();
+        data[8]^=1;
+        assert!(matches!(verify(&data),Err(SaveError::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn truncated(){
+        let data=write_fields();
+
+        // Обрезанный файл не проходит проверку суммы
+        assert!(matches!(verify(&data[..data.len()-1]),Err(SaveError::ChecksumMismatch)));
+        assert!(matches!(verify(&data[..3]),Err(SaveError::Binary(BinaryError::UnexpectedEnd))));
+
+        // Обрезанные поля
+        let content=verify(&data).unwrap();
+        let mut reader=BinaryReader::new(&content[6..20]);
+        assert!(matches!(read_bookmark(&mut reader),Err(SaveError::Binary(BinaryError::UnexpectedEnd))));
+    }
+
+    #[test]
+    fn invalid_variable_type(){
+        let mut writer=BinaryWriter::new();
+        writer.write_u32(1);
+        writer.write_str("coins");
+        writer.write_u8(2);
+
+        let data=writer.into_bytes();
+        assert!(matches!(read_variables(&mut BinaryReader::new(&data)),Err(SaveError::InvalidValue(_))));
+    }
+}
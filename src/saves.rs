@@ -0,0 +1,289 @@
+use crate::{
+    // statics
+    game_settings,
+    // structs
+    save_format::{
+        SaveError,
+        begin,
+        finish,
+        verify,
+        load_with_backup,
+        write_bookmark,
+        read_bookmark,
+        write_choices,
+        read_choices,
+        write_variables,
+        read_variables,
+    },
+};
+
+use lib::{
+    story::{Variables,Gender,Bookmark},
+    binary::BinaryReader,
+    atomic_file::write_atomic,
+};
+
+use cat_engine::image::{
+    RgbaImage,
+    imageops::{resize,FilterType},
+};
+
+use std::{
+    fs::metadata,
+    time::{SystemTime,UNIX_EPOCH},
+};
+
+/// Количество слотов сохранения.
+pub const save_slots:usize=6;
+
+/// Количество слотов автосохранения.
+pub const autosave_slots:usize=3;
+
+/// Размер миниатюры экрана в слоте.
+pub const thumbnail_size:[u32;2]=[320,180];
+
+const saves_path:&'static str="./saves";
+
+/// Отдельный слот быстрого сохранения.
+pub const quick_save_path:&'static str="./saves/quick";
+
+/// Начало файла сохранения.
+const save_magic:&[u8;4]=b"VNSV";
+/// Версия 2 хранит место в истории вместо названия страницы и номера строки.
+const save_version:u16=2;
+
+/// Сохранение игры в слоте (`./saves/slot[номер]`).
+pub struct SaveSlot{
+    /// Место в истории
+    pub position:Bookmark,
+    pub user_name:String,
+    pub gender:Gender,
+    pub choices:Vec<(String,usize)>,
+    pub variables:Variables,
+    /// Время сохранения в секундах с начала эпохи UNIX
+    pub timestamp:u64,
+    /// Время игры в секундах
+    pub playtime:u64,
+    /// Миниатюра экрана размером `thumbnail_size`
+    pub thumbnail:RgbaImage,
+}
+
+impl SaveSlot{
+    /// Текущее положение в игре из `game_settings`.
+    /// `screen` - снимок экрана любого размера.
+    pub fn capture(screen:&RgbaImage)->SaveSlot{
+        let timestamp=SystemTime::now().duration_since(UNIX_EPOCH).map_or(0,|time|time.as_secs());
+        let [width,height]=thumbnail_size;
+
+        unsafe{
+            Self{
+                position:game_settings.saved_position.clone(),
+                user_name:game_settings.user_name.clone(),
+                gender:game_settings.gender,
+                choices:game_settings.choices.clone(),
+                variables:game_settings.variables.clone(),
+                timestamp,
+                playtime:game_settings.playtime,
+                thumbnail:resize(screen,width,height,FilterType::Triangle),
+            }
+        }
+    }
+
+    /// Путь файла слота, номера начинаются с нуля.
+    pub fn path(slot:usize)->String{
+        format!("{}/slot{}",saves_path,slot+1)
+    }
+
+    /// Путь файла слота автосохранения, номера начинаются с нуля.
+    pub fn autosave_path(slot:usize)->String{
+        format!("{}/auto{}",saves_path,slot+1)
+    }
+
+    /// Запись в самый старый по времени изменения слот автосохранения.
+    /// Пустые слоты занимаются первыми.
+    pub fn autosave(&self)->Result<(),SaveError>{
+        let slot=(0..autosave_slots).min_by_key(|&slot|{
+            metadata(SaveSlot::autosave_path(slot)).and_then(|file|file.modified()).ok()
+        }).unwrap_or(0);
+
+        self.save_file(&SaveSlot::autosave_path(slot))
+    }
+
+    /// `None` - файла нет (слот пуст).
+    pub fn load_file(path:&str)->Result<Option<SaveSlot>,SaveError>{
+        load_with_backup(path,SaveSlot::from_bytes)
+    }
+
+    pub fn save_file(&self,path:&str)->Result<(),SaveError>{
+        write_atomic(path,&self.to_bytes(),|data|SaveSlot::from_bytes(data).is_ok())?;
+        Ok(())
+    }
+
+    /// Перенос сохранения в `game_settings` для продолжения с него.
+    /// Место в изменённой истории находится при переходе на страницу игры.
+    pub fn restore(&self){
+        unsafe{
+            game_settings.continue_game=true;
+            game_settings.user_name=self.user_name.clone();
+            game_settings.gender=self.gender;
+            game_settings.choices=self.choices.clone();
+            game_settings.variables=self.variables.clone();
+            game_settings.playtime=self.playtime;
+            game_settings.set_saved_position(self.position.clone());
+        }
+    }
+
+    pub fn to_bytes(&self)->Vec<u8>{
+        let mut writer=begin(save_magic,save_version);
+
+        write_bookmark(&mut writer,&self.position);
+        writer.write_str(&self.user_name);
+        writer.write_u8(self.gender.to_u8());
+        write_choices(&mut writer,&self.choices);
+        write_variables(&mut writer,&self.variables);
+        writer.write_u64(self.timestamp);
+        writer.write_u64(self.playtime);
+
+        // Миниатюра: ширина, высота и пиксели RGBA
+        writer.write_u32(self.thumbnail.width());
+        writer.write_u32(self.thumbnail.height());
+        writer.write_raw(self.thumbnail.as_raw());
+
+        finish(writer)
+    }
+
+    pub fn from_bytes(data:&[u8])->Result<SaveSlot,SaveError>{
+        if !data.starts_with(save_magic){
+            return Err(SaveError::InvalidValue("magic"))
+        }
+
+        let mut reader=BinaryReader::new(verify(data)?);
+        reader.read_raw(save_magic.len())?;
+
+        let version=reader.read_u16()?;
+        let position=match version{
+            // Название страницы и номер строки
+            1=>{
+                let mut position=Bookmark::new();
+                position.page_label=reader.read_string()?;
+                position.line=reader.read_u64()? as usize;
+                position
+            }
+            2=>read_bookmark(&mut reader)?,
+            _=>return Err(SaveError::UnsupportedVersion(version)),
+        };
+        let user_name=reader.read_string()?;
+        let gender=Gender::from_u8(reader.read_u8()?).ok_or(SaveError::InvalidValue("gender"))?;
+        let choices=read_choices(&mut reader)?;
+        let variables=read_variables(&mut reader)?;
+        let timestamp=reader.read_u64()?;
+        let playtime=reader.read_u64()?;
+
+        let width=reader.read_u32()?;
+        let height=reader.read_u32()?;
+        let pixels=reader.read_raw(width as usize*height as usize*4)?;
+        let thumbnail=RgbaImage::from_raw(width,height,pixels.to_vec())
+                .ok_or(SaveError::InvalidValue("thumbnail"))?;
+
+        Ok(Self{
+            position,
+            user_name,
+            gender,
+            choices,
+            variables,
+            timestamp,
+            playtime,
+            thumbnail,
+        })
+    }
+
+    /// Дата и время сохранения (UTC): `ДД.ММ.ГГГГ ЧЧ:ММ`.
+    pub fn date(&self)->String{
+        let days=(self.timestamp/86400) as i64;
+        let seconds=self.timestamp%86400;
+
+        // Перевод дней с начала эпохи в дату григорианского календаря
+        let z=days+719468;
+        let era=z.div_euclid(146097);
+        let day_of_era=z.rem_euclid(146097);
+        let year_of_era=(day_of_era-day_of_era/1460+day_of_era/36524-day_of_era/146096)/365;
+        let day_of_year=day_of_era-(365*year_of_era+year_of_era/4-year_of_era/100);
+        let month_index=(5*day_of_year+2)/153;
+        let day=day_of_year-(153*month_index+2)/5+1;
+        let month=if month_index<10{month_index+3}else{month_index-9};
+        let year=year_of_era+era*400+if month<=2{1}else{0};
+
+        format!("{:02}.{:02}.{} {:02}:{:02}",day,month,year,seconds/3600,seconds%3600/60)
+    }
+
+    /// Время игры: `Ч:ММ:СС`.
+    pub fn playtime(&self)->String{
+        format!("{}:{:02}:{:02}",self.playtime/3600,self.playtime%3600/60,self.playtime%60)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    use lib::story::Value;
+
+    fn slot()->SaveSlot{
+        let mut variables=Variables::new();
+        variables.set("brave",Value::Boolean(true));
+
+        SaveSlot{
+            position:Bookmark{
+                page_label:"bridge".to_string(),
+                page:1,
+                line:5,
+                anchor:None,
+                hash:Some(7),
+            },
+            user_name:"Ника".to_string(),
+            gender:Gender::Female,
+            choices:vec![("start".to_string(),0)],
+            variables,
+            timestamp:1_700_000_000,
+            playtime:125,
+            thumbnail:RgbaImage::from_pixel(4,2,cat_engine::image::Rgba([1,2,3,255])),
+        }
+    }
+
+    #[test]
+    fn round_trip(){
+        let slot=slot();
+        let loaded=SaveSlot::from_bytes(&slot.to_bytes()).unwrap();
+
+        assert_eq!(loaded.position,slot.position);
+        assert_eq!(loaded.user_name,slot.user_name);
+        assert_eq!(loaded.gender,slot.gender);
+        assert_eq!(loaded.choices,slot.choices);
+        assert_eq!(loaded.variables,slot.variables);
+        assert_eq!(loaded.timestamp,slot.timestamp);
+        assert_eq!(loaded.playtime,slot.playtime);
+        assert_eq!(loaded.thumbnail,slot.thumbnail);
+
+        assert_eq!(loaded.date(),"14.11.2023 22:13");
+        assert_eq!(loaded.playtime(),"0:02:05");
+    }
+
+    #[test]
+    fn unsupported_version(){
+        // Версия с верной контрольной суммой
+        let mut writer=begin(save_magic,save_version+1);
+        write_bookmark(&mut writer,&Bookmark::new());
+        let data=finish(writer);
+
+        assert!(matches!(SaveSlot::from_bytes(&data),Err(SaveError::UnsupportedVersion(version)) if version==save_version+1));
+    }
+
+    #[test]
+    fn truncated_or_corrupt(){
+        let data=slot().to_bytes();
+
+        assert!(matches!(SaveSlot::from_bytes(&data[..data.len()-1]),Err(SaveError::ChecksumMismatch)));
+        assert!(SaveSlot::from_bytes(b"VNSV").is_err());
+        assert!(matches!(SaveSlot::from_bytes(b"SAVE"),Err(SaveError::InvalidValue("magic"))));
+    }
+}